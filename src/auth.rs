@@ -0,0 +1,349 @@
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use thiserror::Error;
+
+/// The environment variables a token for github.com is read from, in order.
+const GITHUB_TOKEN_ENV_VARS: &[&str] = &["GITHUB_TOKEN", "GH_TOKEN"];
+
+/// The environment variables a token for any other host, i.e. a GitHub Enterprise Server,
+/// is read from, in order. These are the ones the GitHub CLI uses.
+const ENTERPRISE_TOKEN_ENV_VARS: &[&str] = &["GH_ENTERPRISE_TOKEN", "GITHUB_ENTERPRISE_TOKEN"];
+
+/// The service the token is stored under in the OS keyring. The account is the host.
+pub const KEYRING_SERVICE: &str = "reposouls";
+
+/// Where a token was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenSource {
+    /// Passed to [`Credentials::with_token`].
+    Given,
+    /// An environment variable.
+    Env(&'static str),
+    /// The GitHub CLI's `hosts.yml`.
+    GhCli,
+    /// `git credential fill`, i.e. the configured git credential helper.
+    GitCredential,
+    /// The OS keyring.
+    Keyring,
+}
+
+impl fmt::Display for TokenSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenSource::Given => write!(f, "the caller"),
+            TokenSource::Env(name) => write!(f, "${}", name),
+            TokenSource::GhCli => write!(f, "the gh CLI"),
+            TokenSource::GitCredential => write!(f, "the git credential helper"),
+            TokenSource::Keyring => write!(f, "the OS keyring"),
+        }
+    }
+}
+
+/// A token for the GitHub API and where it came from.
+#[derive(Clone)]
+pub struct Credential {
+    /// The token.
+    pub token: String,
+    /// Where the token was found.
+    pub source: TokenSource,
+}
+
+impl fmt::Debug for Credential {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credential")
+            .field("token", &"<redacted>")
+            .field("source", &self.source)
+            .finish()
+    }
+}
+
+/// No token could be found for a host.
+#[derive(Debug, Error)]
+#[error(
+    "No GitHub token found for {host}. Set {env_vars}, run `gh auth login --hostname \
+     {host}`, {more}.",
+    env_vars = token_env_vars(.host).join(" or "),
+    more = other_sources_hint(.host)
+)]
+pub struct AuthError {
+    /// The host a token was looked for.
+    pub host: String,
+}
+
+/// The tokens found so far, by host.
+///
+/// Tokens are looked up once per host, trying in order the `GITHUB_TOKEN` and `GH_TOKEN`
+/// environment variables for github.com, or `GH_ENTERPRISE_TOKEN` and
+/// `GITHUB_ENTERPRISE_TOKEN` for other hosts, the GitHub CLI's `hosts.yml`, the git
+/// credential helper and, with the `keyring` feature, the OS keyring.
+#[derive(Debug, Default)]
+pub struct Credentials {
+    found: Mutex<HashMap<String, Credential>>,
+}
+
+impl Credentials {
+    /// Creates an empty set of credentials.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates credentials holding a token for `host`, which is then used instead of
+    /// looking one up. Tokens for other hosts are still looked up.
+    ///
+    /// # Arguments
+    ///
+    /// * `host` - The host serving the repositories, e.g. `github.com`.
+    /// * `token` - The token to use for `host`.
+    pub fn with_token(host: &str, token: String) -> Self {
+        let credentials = Self::new();
+        credentials.found.lock().unwrap().insert(
+            host.to_ascii_lowercase(),
+            Credential {
+                token,
+                source: TokenSource::Given,
+            },
+        );
+        credentials
+    }
+
+    /// Returns the token for `host`, looking it up the first time.
+    ///
+    /// Looking a token up may run `git credential fill` or wait on the OS keyring, so async
+    /// code should call this from a blocking task.
+    ///
+    /// # Arguments
+    ///
+    /// * `host` - The host serving the repositories, e.g. `github.com`.
+    pub fn token_for(&self, host: &str) -> Result<String, AuthError> {
+        let host = host.to_ascii_lowercase();
+        if let Some(credential) = self.found.lock().unwrap().get(&host) {
+            return Ok(credential.token.clone());
+        }
+
+        let credential = find_credential(&host).ok_or_else(|| AuthError { host: host.clone() })?;
+        println!(
+            "Using the GitHub token for {} from {}",
+            host, credential.source
+        );
+        let token = credential.token.clone();
+        self.found.lock().unwrap().insert(host, credential);
+        Ok(token)
+    }
+}
+
+/// Returns the environment variables a token for `host` is read from, in order.
+///
+/// Those for github.com are often set for other purposes, e.g. in CI, so they are never
+/// sent to another host.
+fn token_env_vars(host: &str) -> &'static [&'static str] {
+    if host.eq_ignore_ascii_case("github.com") {
+        GITHUB_TOKEN_ENV_VARS
+    } else {
+        ENTERPRISE_TOKEN_ENV_VARS
+    }
+}
+
+/// Looks a token for `host` up in every source, in order.
+fn find_credential(host: &str) -> Option<Credential> {
+    let from_env = token_env_vars(host).iter().find_map(|name| {
+        non_empty(env::var(name).ok()?).map(|token| Credential {
+            token,
+            source: TokenSource::Env(name),
+        })
+    });
+    from_env
+        .or_else(|| with_source(gh_cli_token(host), TokenSource::GhCli))
+        .or_else(|| with_source(git_credential_token(host), TokenSource::GitCredential))
+        .or_else(|| with_source(keyring_token(host), TokenSource::Keyring))
+}
+
+fn with_source(token: Option<String>, source: TokenSource) -> Option<Credential> {
+    token.map(|token| Credential { token, source })
+}
+
+fn non_empty(token: String) -> Option<String> {
+    let token = token.trim();
+    (!token.is_empty()).then(|| token.to_string())
+}
+
+/// Returns the directory the GitHub CLI keeps its configuration in.
+fn gh_config_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("GH_CONFIG_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    if let Some(dir) = env::var_os("XDG_CONFIG_HOME") {
+        return Some(PathBuf::from(dir).join("gh"));
+    }
+    if cfg!(windows)
+        && let Some(dir) = env::var_os("AppData")
+    {
+        return Some(PathBuf::from(dir).join("GitHub CLI"));
+    }
+    dirs::home_dir().map(|home| home.join(".config").join("gh"))
+}
+
+/// Reads the token the GitHub CLI stored in plain text for `host`.
+///
+/// Recent versions of `gh` keep the token in the OS keyring instead, in which case
+/// `hosts.yml` has none and this returns `None`.
+fn gh_cli_token(host: &str) -> Option<String> {
+    let contents = fs::read_to_string(gh_config_dir()?.join("hosts.yml")).ok()?;
+    parse_gh_hosts(&contents, host)
+}
+
+/// Finds the `oauth_token` in the block of `host` in a `hosts.yml` such as:
+///
+/// ```yaml
+/// github.com:
+///     user: octocat
+///     oauth_token: gho_xxx
+/// ```
+fn parse_gh_hosts(contents: &str, host: &str) -> Option<String> {
+    let unquote = |value: &str| {
+        value
+            .trim()
+            .trim_matches(|c| c == '"' || c == '\'')
+            .to_string()
+    };
+    let mut in_host = false;
+    for line in contents.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if !line.starts_with([' ', '\t']) {
+            in_host = trimmed
+                .strip_suffix(':')
+                .is_some_and(|name| unquote(name).eq_ignore_ascii_case(host));
+            continue;
+        }
+        if in_host
+            && let Some((key, value)) = trimmed.split_once(':')
+            && key.trim() == "oauth_token"
+        {
+            return non_empty(unquote(value));
+        }
+    }
+    None
+}
+
+/// Asks the configured git credential helper for a password for `https://<host>`.
+///
+/// Prompting is disabled, so this never blocks on user input.
+fn git_credential_token(host: &str) -> Option<String> {
+    let mut child = Command::new("git")
+        .args(["-c", "core.askPass=", "credential", "fill"])
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .env_remove("GIT_ASKPASS")
+        .env_remove("SSH_ASKPASS")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    child
+        .stdin
+        .take()?
+        .write_all(format!("protocol=https\nhost={}\n\n", host).as_bytes())
+        .ok()?;
+    let output = child.wait_with_output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.strip_prefix("password="))
+        .and_then(|password| non_empty(password.to_string()))
+}
+
+/// Reads the token stored in the OS keyring for `host`.
+#[cfg(feature = "keyring")]
+fn keyring_token(host: &str) -> Option<String> {
+    let entry = keyring::Entry::new(KEYRING_SERVICE, host).ok()?;
+    entry.get_password().ok().and_then(non_empty)
+}
+
+/// Built without the `keyring` feature, there is no keyring to read.
+#[cfg(not(feature = "keyring"))]
+fn keyring_token(_host: &str) -> Option<String> {
+    None
+}
+
+/// Describes the sources after `gh`, for the error when no token was found.
+fn other_sources_hint(host: &str) -> String {
+    let helper = format!(
+        "store a token for https://{} with a git credential helper",
+        host
+    );
+    if cfg!(feature = "keyring") {
+        format!(
+            "{}, or add one to the OS keyring with service \"{}\" and account \"{}\"",
+            helper, KEYRING_SERVICE, host
+        )
+    } else {
+        format!("or {}", helper)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_token_of_the_requested_host() {
+        let hosts = "\
+github.com:
+    user: octocat
+    oauth_token: gho_public
+    git_protocol: https
+\"github.example.com\":
+    oauth_token: 'gho_enterprise'
+";
+        assert_eq!(
+            parse_gh_hosts(hosts, "github.com").as_deref(),
+            Some("gho_public")
+        );
+        assert_eq!(
+            parse_gh_hosts(hosts, "github.example.com").as_deref(),
+            Some("gho_enterprise")
+        );
+        assert_eq!(parse_gh_hosts(hosts, "gitlab.com"), None);
+    }
+
+    #[test]
+    fn environment_tokens_are_only_used_for_their_host() {
+        assert_eq!(token_env_vars("GitHub.com"), ["GITHUB_TOKEN", "GH_TOKEN"]);
+        assert_eq!(
+            token_env_vars("github.example.com"),
+            ["GH_ENTERPRISE_TOKEN", "GITHUB_ENTERPRISE_TOKEN"]
+        );
+        let error = AuthError {
+            host: "github.example.com".to_string(),
+        };
+        assert!(
+            error
+                .to_string()
+                .contains("Set GH_ENTERPRISE_TOKEN or GITHUB_ENTERPRISE_TOKEN,"),
+            "unexpected message: {}",
+            error
+        );
+    }
+
+    #[test]
+    fn hosts_without_a_plain_text_token_have_none() {
+        let hosts = "\
+github.com:
+    git_protocol: https
+    users:
+        octocat:
+    user: octocat
+";
+        assert_eq!(parse_gh_hosts(hosts, "github.com"), None);
+    }
+}
@@ -0,0 +1,125 @@
+use crate::target::Target;
+
+/// The usage text printed for `--help`.
+pub const USAGE: &str = "\
+Usage: reposouls [TARGET]...
+
+Watches GitHub for CI results, reviews, comments and merges and announces them.
+
+Targets:
+  PATH[@BRANCH]               A local checkout. Follows the checked-out branch unless
+                              BRANCH is given.
+  [HOST/]OWNER/REPO@BRANCH    A branch of a repository on GitHub.
+
+Without targets, the watch-list from the configuration file is used, or the current
+directory if it has none.
+
+Authentication:
+  The GitHub token for each host is taken from the first of:
+    the GITHUB_TOKEN or GH_TOKEN environment variable, for github.com,
+    the GH_ENTERPRISE_TOKEN or GITHUB_ENTERPRISE_TOKEN environment variable, for
+      other hosts,
+    the gh CLI's hosts.yml (after `gh auth login`),
+    the git credential helper (`git credential fill` for https://HOST),
+    the OS keyring, with service \"reposouls\" and the host as account.
+
+Options:
+      --catch-up              Report events missed since the last run (the default).
+      --no-catch-up           Only report events from now on.
+      --headless              Print events in the terminal instead of on screen, e.g.
+                              over SSH or on machines without a display.
+  -h, --help                  Print this help.
+";
+
+/// The parsed command-line arguments.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Args {
+    /// The targets given on the command line.
+    pub targets: Vec<Target>,
+    /// Whether to catch up on missed events, overriding `catch_up.enabled`.
+    pub catch_up: Option<bool>,
+    /// Whether to print events in the terminal instead of opening the overlay.
+    pub headless: bool,
+    /// Whether `--help` was requested.
+    pub help: bool,
+}
+
+impl Args {
+    /// Parses the command-line arguments, excluding the program name.
+    ///
+    /// # Arguments
+    ///
+    /// * `args` - The arguments to parse.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Args::default();
+        for arg in args {
+            match arg.as_str() {
+                "-h" | "--help" => parsed.help = true,
+                "--catch-up" => parsed.catch_up = Some(true),
+                "--no-catch-up" => parsed.catch_up = Some(false),
+                "--headless" => parsed.headless = true,
+                option if option.starts_with('-') => {
+                    return Err(format!("Unknown option '{}'. See --help.", option));
+                }
+                spec => parsed.targets.push(Target::parse(spec)?),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        Args::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parses_options_and_targets() {
+        let repo = Target::parse("owner/repo@main").unwrap();
+        let cases = [
+            (&[][..], Ok(Args::default())),
+            (
+                &["-h"],
+                Ok(Args {
+                    help: true,
+                    ..Args::default()
+                }),
+            ),
+            (
+                &["--headless", "--no-catch-up", "owner/repo@main", "."],
+                Ok(Args {
+                    targets: vec![repo.clone(), Target::current_dir()],
+                    catch_up: Some(false),
+                    headless: true,
+                    help: false,
+                }),
+            ),
+            // The last of --catch-up and --no-catch-up wins.
+            (
+                &["--no-catch-up", "--catch-up"],
+                Ok(Args {
+                    catch_up: Some(true),
+                    ..Args::default()
+                }),
+            ),
+            (
+                &["--verbose", "owner/repo@main"],
+                Err("Unknown option '--verbose'. See --help.".to_string()),
+            ),
+            (
+                &["owner/repo"],
+                Err(
+                    "Invalid target 'owner/repo': a branch is required for repositories, \
+                     e.g. owner/repo@main"
+                        .to_string(),
+                ),
+            ),
+        ];
+        for (args, expected) in cases {
+            assert_eq!(parse(args), expected, "parsing {:?}", args);
+        }
+    }
+}
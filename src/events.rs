@@ -1,13 +1,28 @@
-use crate::git::{GitInfo, get_git_info};
-use crate::github::{GitHubClient, ReviewState, WorkflowRunConclusion, WorkflowRunStatus};
+use crate::auth::{AuthError, Credentials};
+use crate::config::Config;
+use crate::git::{GitInfo, GitInfoError, RepoSlug, get_git_info};
+use crate::github::{
+    GitHubClient, GitHubError, RetryPolicy, ReviewState, SharedRateLimit, WorkflowRun,
+    WorkflowRunConclusion, WorkflowRunStatus,
+};
+use crate::sink::{EventSink, SinkError};
+use crate::state::{SavedState, state_path};
+use crate::status::Status;
+use crate::target::Target;
 use chrono::{DateTime, Utc};
-use std::collections::HashSet;
-use std::env;
-use std::sync::mpsc;
-use tokio::time::{self, Duration};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::panic;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use thiserror::Error;
+use tokio::task::{self, JoinSet};
+use tokio::time;
 
-#[derive(Debug, PartialEq, Eq)]
-pub enum NotificationEvent {
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
     CiSuccess,
     CiFailure,
     PrApproved,
@@ -16,147 +31,1067 @@ pub enum NotificationEvent {
     PrNewComment,
 }
 
+impl NotificationKind {
+    /// Every kind of notification event.
+    pub const ALL: [NotificationKind; 6] = [
+        NotificationKind::CiSuccess,
+        NotificationKind::CiFailure,
+        NotificationKind::PrApproved,
+        NotificationKind::PrChangesRequested,
+        NotificationKind::PrMerged,
+        NotificationKind::PrNewComment,
+    ];
+
+    /// Returns the built-in priority of the event. Higher values are more important.
+    ///
+    /// Failures and change requests need attention and come first; comments, which can
+    /// be frequent, come last.
+    pub fn default_priority(&self) -> u8 {
+        match self {
+            NotificationKind::CiFailure | NotificationKind::PrChangesRequested => 30,
+            NotificationKind::PrApproved | NotificationKind::PrMerged => 20,
+            NotificationKind::CiSuccess => 10,
+            NotificationKind::PrNewComment => 0,
+        }
+    }
+
+    /// Returns the key used for this event in the configuration file.
+    pub fn key(&self) -> &'static str {
+        match self {
+            NotificationKind::CiSuccess => "ci_success",
+            NotificationKind::CiFailure => "ci_failure",
+            NotificationKind::PrApproved => "pr_approved",
+            NotificationKind::PrChangesRequested => "pr_changes_requested",
+            NotificationKind::PrMerged => "pr_merged",
+            NotificationKind::PrNewComment => "pr_new_comment",
+        }
+    }
+}
+
+/// The API requests a poll of a target is assumed to cost at least, for working out the
+/// polling rate the rate limit can sustain. Polls reading several pages cost more.
+const REQUESTS_PER_POLL: u32 = 5;
+
+/// The rate-limit budgets of the APIs in use, by API base URL.
+///
+/// Every host has a single token, so the targets on a host share its budget.
+type RateLimits = Mutex<HashMap<String, SharedRateLimit>>;
+
+/// How far the listing cursors trail the last successful check.
+///
+/// The lag absorbs clock differences between this machine and GitHub, and since the
+/// cursors only move in steps of at least this much, the listing URLs stay the same for a
+/// while, so unchanged listings are answered with a cheap `304 Not Modified`.
+const CURSOR_LAG: chrono::Duration = chrono::Duration::minutes(10);
+
+/// The maximum number of characters of a comment carried in an event.
+const COMMENT_EXCERPT_CHARS: usize = 140;
+
+/// A notification produced by the event checker.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct NotificationEvent {
+    /// What happened.
+    pub kind: NotificationKind,
+    /// How important the event is, as configured for its kind. Higher values are more
+    /// important.
+    pub priority: u8,
+    /// How many events this one stands for. More than one when events missed while
+    /// reposouls was not running are summarised, or when queued notifications are
+    /// coalesced; the payload is that of the latest.
+    pub count: usize,
+    /// The details of what happened.
+    pub payload: EventPayload,
+}
+
+impl NotificationEvent {
+    /// Describes the event in one line, e.g. `#142 Add retry logic — approved by alice`
+    /// or `CI: build-and-test failed on feature/x`, or `3 new comments on #142 Add retry
+    /// logic` when it stands for several events.
+    pub fn summary(&self) -> String {
+        if self.count > 1 {
+            return self.summary_of_many(self.count);
+        }
+
+        let payload = &self.payload;
+        let location = payload.branch.as_deref().unwrap_or(&payload.repo);
+        let workflow = payload.workflow_name.as_deref().unwrap_or("workflow");
+        let by = |verb: &str| match &payload.actor {
+            Some(actor) => format!("{} by {}", verb, actor),
+            None => verb.to_string(),
+        };
+
+        let detail = match self.kind {
+            NotificationKind::CiSuccess => {
+                return format!("CI: {} passed on {}", workflow, location);
+            }
+            NotificationKind::CiFailure => {
+                return format!("CI: {} failed on {}", workflow, location);
+            }
+            NotificationKind::PrApproved => by("approved"),
+            NotificationKind::PrChangesRequested => by("changes requested"),
+            NotificationKind::PrMerged => by("merged"),
+            NotificationKind::PrNewComment => match (&payload.actor, &payload.comment_excerpt) {
+                (Some(actor), Some(excerpt)) => format!("{}: “{}”", actor, excerpt),
+                (Some(actor), None) => format!("new comment by {}", actor),
+                (None, Some(excerpt)) => format!("“{}”", excerpt),
+                (None, None) => "new comment".to_string(),
+            },
+        };
+
+        match (payload.pr_number, &payload.pr_title) {
+            (Some(number), Some(title)) => format!("#{} {} — {}", number, title, detail),
+            (Some(number), None) => format!("#{} — {}", number, detail),
+            _ => format!("{} — {}", location, detail),
+        }
+    }
+
+    fn summary_of_many(&self, count: usize) -> String {
+        let what = match self.kind {
+            NotificationKind::CiSuccess => "CI runs passed",
+            NotificationKind::CiFailure => "CI runs failed",
+            NotificationKind::PrApproved => "approvals",
+            NotificationKind::PrChangesRequested => "change requests",
+            NotificationKind::PrMerged => "merges",
+            NotificationKind::PrNewComment => "new comments",
+        };
+        let payload = &self.payload;
+        match (payload.pr_number, &payload.pr_title, &payload.branch) {
+            (Some(number), Some(title), _) => {
+                format!("{} {} on #{} {}", count, what, number, title)
+            }
+            (Some(number), None, _) => format!("{} {} on #{}", count, what, number),
+            (None, _, Some(branch)) => format!("{} {} on {}", count, what, branch),
+            (None, _, None) => format!("{} {} on {}", count, what, payload.repo),
+        }
+    }
+}
+
+#[cfg(test)]
+impl NotificationEvent {
+    /// An event of `kind` for tests, about pull request #7 "Add retry logic" on
+    /// `feature/x` of `owner/repo`. Tests set the fields they care about.
+    pub(crate) fn example(kind: NotificationKind) -> Self {
+        NotificationEvent {
+            kind,
+            priority: kind.default_priority(),
+            count: 1,
+            payload: EventPayload {
+                target: ".".to_string(),
+                repo: "owner/repo".to_string(),
+                branch: Some("feature/x".to_string()),
+                pr_number: Some(7),
+                pr_title: Some("Add retry logic".to_string()),
+                workflow_name: None,
+                url: None,
+                actor: None,
+                comment_excerpt: None,
+                timestamp: Utc::now(),
+            },
+        }
+    }
+}
+
+/// The context of a notification event. Fields that do not apply to the event are `None`.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct EventPayload {
+    /// The watch-list target the event came from, as shown by its `Display` impl.
+    pub target: String,
+    /// The repository the event happened in, in `owner/repo` form.
+    pub repo: String,
+    /// The branch being watched, or `None` for a detached HEAD.
+    pub branch: Option<String>,
+    /// The number of the pull request.
+    pub pr_number: Option<u64>,
+    /// The title of the pull request.
+    pub pr_title: Option<String>,
+    /// The name of the workflow run.
+    pub workflow_name: Option<String>,
+    /// The URL of the workflow run, review or comment on GitHub.
+    pub url: Option<String>,
+    /// The login of the user who triggered the event.
+    pub actor: Option<String>,
+    /// The start of the comment or review body.
+    pub comment_excerpt: Option<String>,
+    /// When the event happened on GitHub.
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Sends the events of a single target, skipping the kinds disabled in the configuration.
+struct Notifier<'a> {
+    sink: &'a dyn EventSink,
+    config: &'a Config,
+    target: &'a str,
+    /// The events held back while catching up, so they can be summarised if there are many.
+    missed: Mutex<Option<Vec<NotificationEvent>>>,
+}
+
+impl Notifier<'_> {
+    /// Returns a payload describing `git_info`, with the event-specific fields left empty.
+    fn payload(&self, git_info: &GitInfo, timestamp: DateTime<Utc>) -> EventPayload {
+        EventPayload {
+            target: self.target.to_string(),
+            repo: git_info.base.to_string(),
+            branch: git_info.branch.clone(),
+            pr_number: None,
+            pr_title: None,
+            workflow_name: None,
+            url: None,
+            actor: None,
+            comment_excerpt: None,
+            timestamp,
+        }
+    }
+
+    /// Sends an event of the given kind. Returns `false` if the sink no longer takes events.
+    fn notify(&self, kind: NotificationKind, payload: EventPayload) -> bool {
+        if !self.config.is_enabled(&kind) {
+            return true;
+        }
+        let event = NotificationEvent {
+            kind,
+            priority: self.config.priority_for(&kind),
+            count: 1,
+            payload,
+        };
+        if let Some(missed) = self.missed.lock().unwrap().as_mut() {
+            missed.push(event);
+            return true;
+        }
+        self.deliver(event)
+    }
+
+    /// Delivers an event to the sink. Returns `false` if it no longer takes events.
+    fn deliver(&self, event: NotificationEvent) -> bool {
+        match self.sink.deliver(&event) {
+            Ok(()) => true,
+            Err(SinkError::Closed) => false,
+            Err(e) => {
+                eprintln!("[{}] Failed to deliver event: {}", self.target, e);
+                true
+            }
+        }
+    }
+
+    /// Holds events back until [`Notifier::finish_catch_up`] is called.
+    fn begin_catch_up(&self) {
+        *self.missed.lock().unwrap() = Some(Vec::new());
+    }
+
+    /// Sends the events held back since [`Notifier::begin_catch_up`]. More than
+    /// `catch_up.max_events` are summarised into one event per kind and pull request.
+    /// Returns `false` if the sink no longer takes events.
+    fn finish_catch_up(&self) -> bool {
+        let Some(missed) = self.missed.lock().unwrap().take() else {
+            return true;
+        };
+        if missed.is_empty() {
+            return true;
+        }
+        println!(
+            "[{}] Caught up on {} missed event(s)",
+            self.target,
+            missed.len()
+        );
+        let events = if missed.len() > self.config.catch_up.max_events {
+            summarise(missed)
+        } else {
+            missed
+        };
+        events.into_iter().all(|event| self.deliver(event))
+    }
+}
+
+/// Folds events of the same kind on the same pull request into one, keeping the latest
+/// payload and the order in which the groups first appeared.
+fn summarise(events: Vec<NotificationEvent>) -> Vec<NotificationEvent> {
+    let mut summarised: Vec<NotificationEvent> = Vec::new();
+    for event in events {
+        match summarised.iter_mut().find(|summary| {
+            summary.kind == event.kind && summary.payload.pr_number == event.payload.pr_number
+        }) {
+            Some(summary) => {
+                let count = summary.count + event.count;
+                *summary = NotificationEvent { count, ..event };
+            }
+            None => summarised.push(event),
+        }
+    }
+    summarised
+}
+
+/// Shortens a comment to a single-line excerpt of at most `COMMENT_EXCERPT_CHARS` characters.
+/// Returns `None` for empty comments.
+fn excerpt(body: &str) -> Option<String> {
+    let collapsed = body.split_whitespace().collect::<Vec<_>>().join(" ");
+    if collapsed.is_empty() {
+        return None;
+    }
+    if collapsed.chars().count() <= COMMENT_EXCERPT_CHARS {
+        return Some(collapsed);
+    }
+    let truncated: String = collapsed.chars().take(COMMENT_EXCERPT_CHARS - 1).collect();
+    Some(format!("{}…", truncated.trim_end()))
+}
+
 struct EventCheckerState {
+    /// Events before this were either reported by an earlier run or predate watching.
     start_time: DateTime<Utc>,
+    /// Workflow runs created before this are not listed any more.
+    runs_since: DateTime<Utc>,
+    /// Comments not updated since this are not listed any more.
+    comments_since: DateTime<Utc>,
     seen_workflow_runs: HashSet<i64>,
     seen_comments: HashSet<i64>,
     seen_reviews: HashSet<i64>,
-    pr_is_merged: bool,
+    /// The number of the branch's pull request, once it has been merged.
+    merged_pr: Option<u64>,
 }
 
 impl EventCheckerState {
+    /// Picks up from a saved state, so that events since it was saved are reported.
+    fn restore(saved: SavedState) -> Self {
+        Self {
+            start_time: saved.last_checked,
+            runs_since: saved.runs_since.unwrap_or(saved.last_checked),
+            comments_since: saved.comments_since.unwrap_or(saved.last_checked),
+            seen_workflow_runs: saved.seen_workflow_runs,
+            seen_comments: saved.seen_comments,
+            seen_reviews: saved.seen_reviews,
+            merged_pr: saved.merged_pr,
+        }
+    }
+
+    /// Saves the state so that a later run can catch up from `checked_at`.
+    fn save(&self, path: &Path, checked_at: DateTime<Utc>) -> Result<(), String> {
+        SavedState {
+            last_checked: checked_at,
+            seen_workflow_runs: self.seen_workflow_runs.clone(),
+            seen_comments: self.seen_comments.clone(),
+            seen_reviews: self.seen_reviews.clone(),
+            merged_pr: self.merged_pr,
+            runs_since: Some(self.runs_since),
+            comments_since: Some(self.comments_since),
+        }
+        .save(path)
+    }
+
     fn new() -> Self {
+        let now = Utc::now();
         Self {
-            start_time: Utc::now(),
+            start_time: now,
+            runs_since: now,
+            comments_since: now,
             seen_workflow_runs: HashSet::new(),
             seen_comments: HashSet::new(),
             seen_reviews: HashSet::new(),
-            pr_is_merged: false,
+            merged_pr: None,
+        }
+    }
+}
+
+/// Moves a listing cursor up to `to`, less [`CURSOR_LAG`], once that is a full step ahead.
+///
+/// The seen-sets keep what the overlap lists again from being reported twice.
+///
+/// # Arguments
+///
+/// * `cursor` - The cursor to move.
+/// * `to` - The latest time the cursor may reach, e.g. when the listing was last read.
+fn advance(cursor: &mut DateTime<Utc>, to: DateTime<Utc>) {
+    let target = to - CURSOR_LAG;
+    if target >= *cursor + CURSOR_LAG {
+        *cursor = target;
+    }
+}
+
+/// Polls GitHub for events on every target and delivers them to a sink.
+///
+/// Each target is watched by its own task with its own state and client, so a slow or
+/// failing target does not hold up the others. The clients of the targets on a host share
+/// its rate-limit budget.
+///
+/// # Arguments
+///
+/// * `sink` - Where the events are delivered, e.g. the GUI's channel or a
+///   [`FanOut`](crate::sink::FanOut) of several sinks.
+/// * `config` - The configuration.
+/// * `targets` - The watch-list.
+/// * `status` - Where the checker's health is reported for the GUI, and a shutdown is
+///   requested.
+/// * `credentials` - The tokens for the targets' hosts.
+///
+/// Returns once the sink has closed or a shutdown was requested, or with the first error
+/// that stops a target from being watched.
+pub async fn run_event_checker(
+    sink: Arc<dyn EventSink>,
+    config: Config,
+    targets: Vec<Target>,
+    status: Arc<Status>,
+    credentials: Arc<Credentials>,
+) -> Result<(), CheckerError> {
+    let config = Arc::new(config);
+    let rate_limits = Arc::new(RateLimits::default());
+
+    let mut tasks = JoinSet::new();
+    for target in targets {
+        tasks.spawn(watch_target(
+            target,
+            Arc::clone(&config),
+            Arc::clone(&credentials),
+            Arc::clone(&rate_limits),
+            Arc::clone(&sink),
+            Arc::clone(&status),
+        ));
+    }
+    while let Some(result) = tasks.join_next().await {
+        // Every fatal error needs fixing by the user, so stop watching everything.
+        match result {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => return Err(e),
+            Err(e) => return Err(CheckerError::Crashed(e.to_string())),
+        }
+    }
+    Ok(())
+}
+
+/// An error that stops the event checker.
+#[derive(Debug, Error)]
+pub enum CheckerError {
+    /// A target is not a git repository, or its remotes cannot be used.
+    #[error("[{target}] Could not get git info. Is it a git repository? Error: {source}")]
+    GitInfo {
+        target: String,
+        #[source]
+        source: GitInfoError,
+    },
+    /// No token could be found for a target's host.
+    #[error("[{target}] {source}")]
+    NoToken {
+        target: String,
+        #[source]
+        source: AuthError,
+    },
+    /// GitHub rejected the token.
+    #[error(
+        "[{target}] GitHub rejected the token. Make sure the token reposouls uses (see \
+         `reposouls --help`) has not expired and can read the watched repositories' pull \
+         requests and actions. Error: {source}"
+    )]
+    Unauthorized {
+        target: String,
+        #[source]
+        source: GitHubError,
+    },
+    /// A target's task panicked.
+    #[error("The event checker crashed: {0}")]
+    Crashed(String),
+}
+
+/// Why a target stopped being watched.
+enum Stop {
+    /// The sink no longer takes events, e.g. because the GUI went away.
+    SinkClosed,
+    /// GitHub rejected the token.
+    Unauthorized(GitHubError),
+}
+
+impl Stop {
+    /// Converts the reason into the result of watching the target.
+    fn into_result(self, target: &str) -> Result<(), CheckerError> {
+        match self {
+            Stop::SinkClosed => Ok(()),
+            Stop::Unauthorized(source) => Err(CheckerError::Unauthorized {
+                target: target.to_string(),
+                source,
+            }),
+        }
+    }
+}
+
+/// Polls GitHub for the events of a single target until the sink closes, a shutdown is
+/// requested or an error stops it.
+async fn watch_target(
+    target: Target,
+    config: Arc<Config>,
+    credentials: Arc<Credentials>,
+    rate_limits: Arc<RateLimits>,
+    sink: Arc<dyn EventSink>,
+    status: Arc<Status>,
+) -> Result<(), CheckerError> {
+    let label = target.to_string();
+    let git_info = resolve_git_info(&target, &config).map_err(|source| CheckerError::GitInfo {
+        target: label.clone(),
+        source,
+    })?;
+    let (mut git_info, mut client) =
+        match connect(&config, git_info, &credentials, &rate_limits, &label).await {
+            Ok(connected) => connected,
+            Err(source) => {
+                return Err(CheckerError::NoToken {
+                    target: label,
+                    source,
+                });
+            }
+        };
+
+    let notifier = Notifier {
+        sink: sink.as_ref(),
+        config: &config,
+        target: &label,
+        missed: Mutex::new(None),
+    };
+    let mut saved_state_path = state_path(&git_info);
+    let mut state = starting_state(&config, saved_state_path.as_deref(), &notifier);
+    let mut delay = Duration::ZERO;
+    let mut pace = Pace::Normal;
+    let mut last_checked = None;
+
+    loop {
+        // A poll in progress when a shutdown is requested still finishes, so nothing it
+        // reported is reported again on the next start.
+        tokio::select! {
+            biased;
+            _ = status.shutdown_requested() => {
+                if let Some(checked_at) = last_checked {
+                    save_state(&state, saved_state_path.as_deref(), checked_at, &label);
+                }
+                println!("[{}] Stopped", label);
+                return Ok(());
+            }
+            _ = time::sleep(delay) => {}
+        }
+        let checked_at = Utc::now();
+        println!(
+            "[{}] [{}] Checking for events...",
+            Utc::now().format("%H:%M:%S"),
+            label
+        );
+
+        // Re-resolve HEAD on every tick so that `git checkout` is followed without a restart.
+        // Targets pinned to a branch, or not checked out locally, have nothing to follow.
+        if let Target::Local { branch: None, .. } = &target {
+            match resolve_git_info(&target, &config) {
+                Ok(current)
+                    if current.head != git_info.head
+                        || current.host != git_info.host
+                        || current.port != git_info.port =>
+                {
+                    println!(
+                        "[{}] Switched from {} ({}) to {} ({})",
+                        label,
+                        git_info.describe_head(),
+                        git_info.head,
+                        current.describe_head(),
+                        current.head
+                    );
+                    match connect(&config, current, &credentials, &rate_limits, &label).await {
+                        Ok(connected) => {
+                            (git_info, client) = connected;
+                            saved_state_path = state_path(&git_info);
+                            state = starting_state(&config, saved_state_path.as_deref(), &notifier);
+                        }
+                        Err(e) => {
+                            eprintln!("[{}] Still watching the previous branch: {}", label, e)
+                        }
+                    }
+                }
+                Ok(current)
+                    if current.branch != git_info.branch
+                        || (current.branch.is_none() && current.commit != git_info.commit) =>
+                {
+                    println!(
+                        "[{}] Switched from {} to {}",
+                        label,
+                        git_info.describe_head(),
+                        current.describe_head()
+                    );
+                    git_info.branch = current.branch;
+                    git_info.commit = current.commit;
+                    saved_state_path = state_path(&git_info);
+                    state = starting_state(&config, saved_state_path.as_deref(), &notifier);
+                }
+                Ok(current) => git_info.commit = current.commit,
+                Err(e) => eprintln!(
+                    "[{}] Error re-reading git info, still watching {}: {}",
+                    label,
+                    git_info.describe_head(),
+                    e
+                ),
+            }
+        }
+
+        if let Err(stop) =
+            check_workflow_run(&client, &git_info, &mut state, &notifier, checked_at).await
+        {
+            return stop.into_result(&label);
+        }
+
+        // A detached HEAD has no branch to look up pull requests for.
+        if git_info.branch.is_some()
+            && let Err(stop) =
+                check_pr_events(&client, &git_info, &mut state, &notifier, checked_at).await
+        {
+            return stop.into_result(&label);
+        }
+
+        if !notifier.finish_catch_up() {
+            eprintln!("The event sink is closed.");
+            return Ok(());
+        }
+
+        save_state(&state, saved_state_path.as_deref(), checked_at, &label);
+        last_checked = Some(checked_at);
+
+        let offline = client.is_offline();
+        if offline != status.is_target_offline(&label) {
+            if offline {
+                eprintln!("[{}] GitHub is unreachable, will keep trying", label);
+            } else {
+                println!("[{}] GitHub is reachable again", label);
+            }
+            status.set_offline(&label, offline);
+        }
+
+        let previous_pace = pace;
+        (delay, pace) = next_poll(&config, &client, client.take_request_count());
+        if pace != previous_pace {
+            report_pace(&client, pace, delay, &label);
         }
     }
 }
 
-pub async fn run_event_checker(image_sender: mpsc::Sender<NotificationEvent>) {
-    let token = env::var("GITHUB_TOKEN").expect("GITHUB_TOKEN environment variable not set");
-    let git_info = match get_git_info() {
-        Ok(info) => info,
+/// How fast a target is polled, as far as the rate limit is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pace {
+    /// At the configured interval.
+    Normal,
+    /// Slower than configured, so that the remaining budget lasts until it is refilled.
+    Throttled,
+    /// Not at all until the budget is refilled.
+    Paused,
+}
+
+/// Returns how long to wait before polling again, given the rate-limit budget left.
+///
+/// The budget is shared with the other clients using the same token, which are assumed to
+/// poll at the same cost.
+///
+/// # Arguments
+///
+/// * `config` - The configuration, for the polling interval.
+/// * `client` - The target's client.
+/// * `requests` - The requests the last poll counted against the rate limit.
+fn next_poll(config: &Config, client: &GitHubClient, requests: u32) -> (Duration, Pace) {
+    let base = config.poll_interval();
+    let now = Utc::now();
+    if let Some(until) = client.rate_limited_until() {
+        let wait = (until - now).to_std().unwrap_or_default() + Duration::from_secs(1);
+        return (wait.max(base), Pace::Paused);
+    }
+    let Some(rate_limit) = client.rate_limit() else {
+        return (base, Pace::Normal);
+    };
+
+    // Spread the remaining budget evenly over what is left of the window and the clients.
+    let window = (rate_limit.reset - now).to_std().unwrap_or_default();
+    let clients = u32::try_from(client.sharing_clients()).unwrap_or(u32::MAX);
+    let per_poll = requests
+        .max(REQUESTS_PER_POLL)
+        .saturating_mul(clients.max(1));
+    let polls_left = (rate_limit.remaining / per_poll).max(1);
+    let sustainable = window / polls_left;
+    if sustainable > base {
+        (sustainable, Pace::Throttled)
+    } else {
+        (base, Pace::Normal)
+    }
+}
+
+/// Tells the user that the polling pace changed because of the rate limit.
+fn report_pace(client: &GitHubClient, pace: Pace, delay: Duration, label: &str) {
+    match (pace, client.rate_limit()) {
+        (Pace::Paused, _) => println!(
+            "[{}] Rate limit exhausted, pausing for {}s",
+            label,
+            delay.as_secs()
+        ),
+        (Pace::Throttled, Some(rate_limit)) => println!(
+            "[{}] Rate limit running low ({}/{} left until {}), polling every {}s",
+            label,
+            rate_limit.remaining,
+            rate_limit.limit,
+            rate_limit.reset.format("%H:%M:%S UTC"),
+            delay.as_secs()
+        ),
+        _ => println!(
+            "[{}] Rate limit recovered, polling every {}s",
+            label,
+            delay.as_secs()
+        ),
+    }
+}
+
+/// Reports a failed request, or stops watching if GitHub rejected the token.
+///
+/// Exhausted rate limits and an unreachable GitHub are reported once by the polling loop
+/// instead of for every request.
+fn report_error(
+    client: &GitHubClient,
+    target: &str,
+    what: &str,
+    error: GitHubError,
+) -> Result<(), Stop> {
+    match error {
+        GitHubError::Unauthorized { .. } => return Err(Stop::Unauthorized(error)),
+        GitHubError::RateLimited { .. } => {}
+        GitHubError::Network(_) if client.is_offline() => {}
+        GitHubError::Server { status, .. } => eprintln!(
+            "[{}] GitHub failed to return {} ({}), trying again next poll",
+            target, what, status
+        ),
+        error => eprintln!("[{}] Error fetching {}: {}", target, what, error),
+    }
+    Ok(())
+}
+
+/// Saves the state of a branch for the next run, unless it is not kept across restarts.
+fn save_state(
+    state: &EventCheckerState,
+    path: Option<&Path>,
+    checked_at: DateTime<Utc>,
+    label: &str,
+) {
+    if let Some(path) = path
+        && let Err(e) = state.save(path, checked_at)
+    {
+        eprintln!("[{}] Error saving state: {}", label, e);
+    }
+}
+
+/// Returns the state to start watching a branch with.
+///
+/// With catch-up enabled, this is the state saved when the branch was last watched, whether
+/// by an earlier run or before switching away from it, and the events missed since are held
+/// back to be summarised. Otherwise only events from now on are reported.
+///
+/// # Arguments
+///
+/// * `config` - The configuration, for whether to catch up.
+/// * `path` - The branch's state file, or `None` if its state is not kept.
+/// * `notifier` - The target's notifier, which holds back the missed events.
+fn starting_state(
+    config: &Config,
+    path: Option<&Path>,
+    notifier: &Notifier<'_>,
+) -> EventCheckerState {
+    if config.catch_up.enabled
+        && let Some(saved) = load_state(path, notifier.target)
+    {
+        println!(
+            "[{}] Catching up on events since {}",
+            notifier.target,
+            saved.last_checked.format("%Y-%m-%d %H:%M:%S UTC")
+        );
+        notifier.begin_catch_up();
+        return EventCheckerState::restore(saved);
+    }
+    EventCheckerState::new()
+}
+
+/// Reads the state saved for a branch by an earlier run, if there is one.
+fn load_state(path: Option<&Path>, label: &str) -> Option<SavedState> {
+    match SavedState::load(path?) {
+        Ok(saved) => saved,
         Err(e) => {
-            eprintln!(
-                "Fatal: Could not get git info. Are you in a git repository? Error: {}",
-                e
-            );
-            return;
+            eprintln!("[{}] Ignoring saved state: {}", label, e);
+            None
         }
+    }
+}
+
+/// Reads the git information for a target.
+fn resolve_git_info(target: &Target, config: &Config) -> Result<GitInfo, GitInfoError> {
+    match target {
+        Target::Local { path, branch } => get_git_info(
+            path,
+            branch.as_deref(),
+            config.remote.as_deref(),
+            config.base_remote.as_deref(),
+        ),
+        Target::Remote { host, repo, branch } => Ok(GitInfo::for_remote(
+            host.clone(),
+            repo.clone(),
+            branch.clone(),
+        )),
+    }
+}
+
+/// Returns the host serving a target's repository, to look its token up before watching.
+///
+/// # Arguments
+///
+/// * `target` - The target.
+/// * `config` - The configuration, for the remotes to use.
+pub fn resolve_host(target: &Target, config: &Config) -> Result<String, GitInfoError> {
+    resolve_git_info(target, config).map(|git_info| git_info.host)
+}
+
+/// Resolves the repository pull requests are opened against and creates a client for it.
+///
+/// Fails if no token can be found for the repository's host.
+///
+/// # Arguments
+///
+/// * `config` - The configuration, for the hosts' API base URLs.
+/// * `git_info` - The local git state to monitor.
+/// * `credentials` - The tokens used to authenticate with the GitHub API, by host.
+/// * `rate_limits` - The rate-limit budgets the client may share with other targets'.
+/// * `label` - The target's label, for logging.
+async fn connect(
+    config: &Config,
+    mut git_info: GitInfo,
+    credentials: &Arc<Credentials>,
+    rate_limits: &RateLimits,
+    label: &str,
+) -> Result<(GitInfo, GitHubClient), AuthError> {
+    // Looking a token up may run `git credential fill` or wait on the OS keyring.
+    let host = git_info.host.clone();
+    let credentials = Arc::clone(credentials);
+    let token = match task::spawn_blocking(move || credentials.token_for(&host)).await {
+        Ok(token) => token?,
+        Err(e) => panic::resume_unwind(e.into_panic()),
     };
+    let api_base = config.api_base_for(&git_info.host, git_info.port);
+    if git_info.base == git_info.head {
+        git_info.base =
+            resolve_fork_parent(&api_base, &git_info.head, &token, config.retry_policy()).await;
+    }
 
-    let client = GitHubClient::new(git_info.owner.clone(), git_info.repo.clone(), token);
+    let rate_limit = rate_limits
+        .lock()
+        .unwrap()
+        .entry(api_base.clone())
+        .or_default()
+        .clone();
+    let client = GitHubClient::with_shared_rate_limit(
+        api_base.clone(),
+        git_info.base.owner.clone(),
+        git_info.base.repo.clone(),
+        token,
+        config.retry_policy(),
+        rate_limit,
+    );
     println!(
-        "Monitoring repository: {}/{}",
-        git_info.owner, git_info.repo
+        "[{}] Monitoring repository: {} on {}",
+        label, git_info.base, git_info.host
     );
-    println!("Branch: {}", git_info.branch);
-
-    let mut state = EventCheckerState::new();
-    let mut interval = time::interval(Duration::from_secs(10));
+    println!("[{}] API: {}", label, api_base);
+    match &git_info.remote {
+        Some(remote) => println!(
+            "[{}] Branch: {} (remote: {}, pushed to {})",
+            label,
+            git_info.describe_head(),
+            remote,
+            git_info.head
+        ),
+        None => println!("[{}] Branch: {}", label, git_info.describe_head()),
+    }
 
-    loop {
-        interval.tick().await;
-        println!("[{}] Checking for events...", Utc::now().format("%H:%M:%S"));
+    Ok((git_info, client))
+}
 
-        check_workflow_run(&client, &git_info, &mut state, &image_sender).await;
+/// Returns the repository `head` was forked from, or `head` itself if it is not a fork.
+///
+/// Pull requests from a fork are opened against its parent, so that is where they and
+/// their CI runs have to be looked up.
+async fn resolve_fork_parent(
+    api_base: &str,
+    head: &RepoSlug,
+    token: &str,
+    retry_policy: RetryPolicy,
+) -> RepoSlug {
+    let client = GitHubClient::with_retry_policy(
+        api_base.to_string(),
+        head.owner.clone(),
+        head.repo.clone(),
+        token.to_string(),
+        retry_policy,
+    );
+    let repository = match client.get_repository().await {
+        Ok(repository) => repository,
+        Err(e) => {
+            eprintln!("Error fetching repository {}: {}", head, e);
+            return head.clone();
+        }
+    };
 
-        if !state.pr_is_merged {
-            check_pr_events(&client, &git_info, &mut state, &image_sender).await;
+    match repository
+        .parent
+        .and_then(|parent| RepoSlug::from_full_name(&parent.full_name))
+    {
+        Some(parent) => {
+            println!("{} is a fork of {}", head, parent);
+            parent
         }
+        None => head.clone(),
     }
 }
 
+/// Checks for newly completed workflow runs, created since `state.runs_since`.
+/// Stops if the sink has closed or GitHub rejected the token.
+///
+/// `checked_at` is when the poll started; the cursor moves towards it, but never past a run
+/// that has yet to complete.
 async fn check_workflow_run(
     client: &GitHubClient,
     git_info: &GitInfo,
     state: &mut EventCheckerState,
-    sender: &mpsc::Sender<NotificationEvent>,
-) {
-    match client
-        .get_workflow_runs_for_branch(&git_info.branch, state.start_time)
-        .await
-    {
+    notifier: &Notifier<'_>,
+    checked_at: DateTime<Utc>,
+) -> Result<(), Stop> {
+    // Without a branch, fall back to the runs for the checked-out commit.
+    let runs = match (&git_info.branch, &git_info.commit) {
+        (Some(branch), _) => {
+            client
+                .get_workflow_runs_for_branch(branch, state.runs_since)
+                .await
+        }
+        (None, Some(commit)) => {
+            client
+                .get_workflow_runs_for_commit(commit, state.runs_since)
+                .await
+        }
+        (None, None) => return Ok(()),
+    };
+
+    match runs {
         Ok(runs) => {
+            // Listings only include runs created after the cursor, so it must stay before
+            // the earliest run still going.
+            let until = runs
+                .iter()
+                .filter(|run| run.status != WorkflowRunStatus::Completed)
+                .map(|run| run.created_at)
+                .fold(checked_at, DateTime::min);
+            advance(&mut state.runs_since, until);
+
             let new_completed_runs: Vec<_> = runs
                 .into_iter()
                 .filter(|run| {
                     run.status == WorkflowRunStatus::Completed
                         && !state.seen_workflow_runs.contains(&run.id)
+                        && run.head_repository.as_ref().is_none_or(|repository| {
+                            repository
+                                .full_name
+                                .eq_ignore_ascii_case(&git_info.head.to_string())
+                        })
                 })
                 .collect();
 
             if new_completed_runs.is_empty() {
-                return;
+                return Ok(());
             }
 
-            let mut event_to_send: Option<NotificationEvent> = None;
+            let mut event_to_send: Option<(NotificationKind, &WorkflowRun)> = None;
 
-            if new_completed_runs
+            if let Some(run) = new_completed_runs
                 .iter()
-                .any(|run| run.conclusion == Some(WorkflowRunConclusion::Failure))
+                .find(|run| run.conclusion == Some(WorkflowRunConclusion::Failure))
             {
-                println!("CI Failure detected in new workflow runs.");
-                event_to_send = Some(NotificationEvent::CiFailure);
-            } else if new_completed_runs
+                println!(
+                    "[{}] CI Failure detected in new workflow runs.",
+                    notifier.target
+                );
+                event_to_send = Some((NotificationKind::CiFailure, run));
+            } else if let Some(run) = new_completed_runs
                 .iter()
-                .any(|run| run.conclusion == Some(WorkflowRunConclusion::Success))
+                .find(|run| run.conclusion == Some(WorkflowRunConclusion::Success))
             {
-                println!("CI Success detected in new workflow runs.");
-                event_to_send = Some(NotificationEvent::CiSuccess);
+                println!(
+                    "[{}] CI Success detected in new workflow runs.",
+                    notifier.target
+                );
+                event_to_send = Some((NotificationKind::CiSuccess, run));
             }
 
-            if let Some(event) = event_to_send {
-                if sender.send(event).is_err() {
-                    eprintln!("Failed to send to GUI thread.");
-                }
-            }
+            let event = event_to_send.map(|(kind, run)| {
+                let payload = EventPayload {
+                    workflow_name: run.name.clone(),
+                    url: Some(run.html_url.clone()),
+                    actor: run.actor.as_ref().map(|actor| actor.login.clone()),
+                    ..notifier.payload(git_info, run.updated_at)
+                };
+                (kind, payload)
+            });
 
-            for run in new_completed_runs {
+            for run in &new_completed_runs {
                 state.seen_workflow_runs.insert(run.id);
             }
+
+            if let Some((kind, payload)) = event
+                && !notifier.notify(kind, payload)
+            {
+                eprintln!("The event sink is closed.");
+                return Err(Stop::SinkClosed);
+            }
         }
-        Err(e) => eprintln!("Error fetching workflow runs: {}", e),
+        Err(e) => report_error(client, notifier.target, "workflow runs", e)?,
     }
+
+    Ok(())
 }
 
+/// Checks for merges, reviews and comments on the branch's pull request.
+/// Stops if the sink has closed or GitHub rejected the token.
+///
+/// `checked_at` is when the poll started; the comment cursor moves towards it.
 async fn check_pr_events(
     client: &GitHubClient,
     git_info: &GitInfo,
     state: &mut EventCheckerState,
-    sender: &mpsc::Sender<NotificationEvent>,
-) {
-    let pr = match client.get_pr_for_branch(&git_info.branch).await {
+    notifier: &Notifier<'_>,
+    checked_at: DateTime<Utc>,
+) -> Result<(), Stop> {
+    let target = notifier.target;
+    let Some(branch) = &git_info.branch else {
+        return Ok(());
+    };
+    let pr = match client.get_pr_for_branch(&git_info.head.owner, branch).await {
         Ok(Some(pr)) => pr,
-        Ok(None) => return, // No open PR for this branch, this is normal
+        Ok(None) => return Ok(()), // No open PR for this branch, this is normal
         Err(e) => {
-            eprintln!("Error fetching PR for branch: {}", e);
-            return;
+            report_error(client, target, "PR for branch", e)?;
+            return Ok(());
         }
     };
+    // A merged pull request has nothing more to report.
+    if state.merged_pr == Some(pr.number) {
+        return Ok(());
+    }
 
     // Check for merge events
     match client.get_pr_details(pr.number).await {
         Ok(pr_details) => {
             if pr_details.merged == Some(true)
-                && pr_details
-                    .merged_at
-                    .map_or(false, |ts| ts > state.start_time)
+                && pr_details.merged_at.is_some_and(|ts| ts > state.start_time)
             {
-                println!("PR #{} was merged!", pr.number);
-                if sender.send(NotificationEvent::PrMerged).is_err() {
-                    eprintln!("Failed to send to GUI thread. Exiting check_pr_events.");
-                    return;
+                println!("[{}] PR #{} was merged!", target, pr.number);
+                let payload = EventPayload {
+                    pr_number: Some(pr.number),
+                    pr_title: Some(pr.title.clone()),
+                    url: Some(pr_details.html_url.clone()),
+                    actor: pr_details.merged_by.as_ref().map(|user| user.login.clone()),
+                    ..notifier.payload(git_info, pr_details.merged_at.unwrap_or_else(Utc::now))
+                };
+                if !notifier.notify(NotificationKind::PrMerged, payload) {
+                    eprintln!("The event sink is closed. Exiting check_pr_events.");
+                    return Err(Stop::SinkClosed);
                 }
-                state.pr_is_merged = true;
-                return; // PR is merged, no need to check for other PR events
+                state.merged_pr = Some(pr.number);
+                return Ok(()); // PR is merged, no need to check for other PR events
             }
         }
-        Err(e) => eprintln!("Error fetching PR details: {}", e),
+        Err(e) => report_error(client, target, "PR details", e)?,
     }
 
     // Check for new reviews
@@ -166,43 +1101,189 @@ async fn check_pr_events(
                 if !state.seen_reviews.contains(&review.id)
                     && review.submitted_at > state.start_time
                 {
-                    println!("New review found: {}", review.id);
+                    println!("[{}] New review found: {}", target, review.id);
                     let event = match review.state {
-                        ReviewState::Approved => Some(NotificationEvent::PrApproved),
-                        ReviewState::ChangesRequested => {
-                            Some(NotificationEvent::PrChangesRequested)
-                        }
+                        ReviewState::Approved => Some(NotificationKind::PrApproved),
+                        ReviewState::ChangesRequested => Some(NotificationKind::PrChangesRequested),
                         _ => None,
                     };
-                    if let Some(event) = event {
-                        if sender.send(event).is_err() {
-                            eprintln!("Failed to send to GUI thread in check_pr_events.");
-                            return;
+                    if let Some(kind) = event {
+                        let payload = EventPayload {
+                            pr_number: Some(pr.number),
+                            pr_title: Some(pr.title.clone()),
+                            url: Some(review.html_url.clone()),
+                            actor: review.user.as_ref().map(|user| user.login.clone()),
+                            comment_excerpt: review.body.as_deref().and_then(excerpt),
+                            ..notifier.payload(git_info, review.submitted_at)
+                        };
+                        if !notifier.notify(kind, payload) {
+                            eprintln!("The event sink is closed in check_pr_events.");
+                            return Err(Stop::SinkClosed);
                         }
                     }
                     state.seen_reviews.insert(review.id);
                 }
             }
         }
-        Err(e) => eprintln!("Error fetching PR reviews: {}", e),
+        Err(e) => report_error(client, target, "PR reviews", e)?,
     }
 
     // Check for new comments
-    match client.get_pr_comments(pr.number).await {
+    match client
+        .get_pr_comments(pr.number, state.comments_since)
+        .await
+    {
         Ok(comments) => {
             for comment in comments {
                 if !state.seen_comments.contains(&comment.id)
                     && comment.created_at > state.start_time
                 {
-                    println!("New comment found: {}", comment.id);
-                    if sender.send(NotificationEvent::PrNewComment).is_err() {
-                        eprintln!("Failed to send to GUI thread in check_pr_events.");
-                        return;
+                    println!("[{}] New comment found: {}", target, comment.id);
+                    let payload = EventPayload {
+                        pr_number: Some(pr.number),
+                        pr_title: Some(pr.title.clone()),
+                        url: Some(comment.html_url.clone()),
+                        actor: comment.user.as_ref().map(|user| user.login.clone()),
+                        comment_excerpt: excerpt(&comment.body),
+                        ..notifier.payload(git_info, comment.created_at)
+                    };
+                    if !notifier.notify(NotificationKind::PrNewComment, payload) {
+                        eprintln!("The event sink is closed in check_pr_events.");
+                        return Err(Stop::SinkClosed);
                     }
                     state.seen_comments.insert(comment.id);
                 }
             }
+            advance(&mut state.comments_since, checked_at);
+        }
+        Err(e) => report_error(client, target, "PR comments", e)?,
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+    use std::sync::mpsc;
+
+    fn event(kind: NotificationKind, pr_number: u64, actor: &str) -> NotificationEvent {
+        let mut event = NotificationEvent::example(kind);
+        event.payload.pr_number = Some(pr_number);
+        event.payload.actor = Some(actor.to_string());
+        event
+    }
+
+    fn payload(pr_number: u64, actor: &str) -> EventPayload {
+        event(NotificationKind::PrNewComment, pr_number, actor).payload
+    }
+
+    #[test]
+    fn cursors_trail_the_last_check_in_steps() {
+        let start: DateTime<Utc> = "2024-05-01T10:00:00Z".parse().unwrap();
+        let mut cursor = start;
+
+        // Less than a step ahead, so listings keep their URL.
+        advance(&mut cursor, start + chrono::Duration::minutes(15));
+        assert_eq!(cursor, start);
+
+        let checked_at = start + chrono::Duration::minutes(25);
+        advance(&mut cursor, checked_at);
+        assert_eq!(cursor, checked_at - CURSOR_LAG);
+
+        // Never backwards, e.g. for a run that started before the cursor.
+        advance(&mut cursor, start);
+        assert_eq!(cursor, checked_at - CURSOR_LAG);
+    }
+
+    #[test]
+    fn cursors_survive_a_restart() {
+        let dir = TempDir::new("events");
+        let path = dir.path().join("main.json");
+        let checked_at: DateTime<Utc> = "2024-05-01T10:05:00Z".parse().unwrap();
+        // A run started at 10:00 was still going when reposouls stopped.
+        let mut state = EventCheckerState::new();
+        state.runs_since = "2024-05-01T09:59:00Z".parse().unwrap();
+        state.comments_since = "2024-05-01T09:55:00Z".parse().unwrap();
+        state.save(&path, checked_at).unwrap();
+
+        let restored = EventCheckerState::restore(SavedState::load(&path).unwrap().unwrap());
+        assert_eq!(restored.start_time, checked_at);
+        assert_eq!(restored.runs_since, state.runs_since);
+        assert_eq!(restored.comments_since, state.comments_since);
+    }
+
+    #[test]
+    fn summarise_folds_events_by_kind_and_pull_request() {
+        let summarised = summarise(vec![
+            event(NotificationKind::PrNewComment, 1, "alice"),
+            event(NotificationKind::PrApproved, 1, "bob"),
+            event(NotificationKind::PrNewComment, 2, "carol"),
+            event(NotificationKind::PrNewComment, 1, "dave"),
+        ]);
+        let summaries: Vec<_> = summarised
+            .iter()
+            .map(|event| (event.kind, event.count, event.payload.actor.as_deref()))
+            .collect();
+        assert_eq!(
+            summaries,
+            [
+                (NotificationKind::PrNewComment, 2, Some("dave")),
+                (NotificationKind::PrApproved, 1, Some("bob")),
+                (NotificationKind::PrNewComment, 1, Some("carol")),
+            ]
+        );
+        assert_eq!(
+            summarised[0].summary(),
+            "2 new comments on #1 Add retry logic"
+        );
+    }
+
+    #[test]
+    fn missed_events_are_summarised_beyond_the_limit() {
+        let mut config = Config::default();
+        config.catch_up.max_events = 3;
+        let (sender, receiver) = mpsc::channel();
+        let notifier = Notifier {
+            sink: &sender,
+            config: &config,
+            target: ".",
+            missed: Mutex::new(None),
+        };
+
+        // Up to the limit, missed events are delivered as they are.
+        notifier.begin_catch_up();
+        for actor in ["alice", "bob", "carol"] {
+            assert!(notifier.notify(NotificationKind::PrNewComment, payload(1, actor)));
         }
-        Err(e) => eprintln!("Error fetching PR comments: {}", e),
+        assert_eq!(receiver.try_iter().count(), 0);
+        assert!(notifier.finish_catch_up());
+        assert_eq!(
+            receiver.try_iter().map(|event| event.count).sum::<usize>(),
+            3
+        );
+
+        notifier.begin_catch_up();
+        for actor in ["alice", "bob", "carol", "dave"] {
+            notifier.notify(NotificationKind::PrNewComment, payload(1, actor));
+        }
+        notifier.notify(NotificationKind::PrMerged, payload(1, "erin"));
+        assert!(notifier.finish_catch_up());
+        let delivered: Vec<_> = receiver
+            .try_iter()
+            .map(|event| (event.kind, event.count))
+            .collect();
+        assert_eq!(
+            delivered,
+            [
+                (NotificationKind::PrNewComment, 4),
+                (NotificationKind::PrMerged, 1)
+            ]
+        );
+
+        // Once caught up, events are delivered straight away.
+        notifier.notify(NotificationKind::CiFailure, payload(1, "frank"));
+        assert_eq!(receiver.try_iter().count(), 1);
     }
 }
@@ -1,58 +1,370 @@
-use git2::Repository;
+use crate::remote::{RemoteUrl, apply_instead_of, parse_remote_url};
+use git2::{Config, Repository};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
 
-#[derive(Debug)]
-pub struct GitInfo {
+/// The remote used when neither the branch nor the configuration names one.
+const DEFAULT_REMOTE: &str = "origin";
+
+/// The remote conventionally pointing at the repository a fork was created from.
+const UPSTREAM_REMOTE: &str = "upstream";
+
+/// An error reading the git information of a checkout.
+#[derive(Debug, Error)]
+pub enum GitInfoError {
+    /// No repository contains the given path.
+    #[error("Failed to open repository at {}: {source}", path.display())]
+    NotARepository {
+        path: PathBuf,
+        #[source]
+        source: git2::Error,
+    },
+    /// Reading the repository failed.
+    #[error("{context}: {source}")]
+    Git {
+        context: &'static str,
+        #[source]
+        source: git2::Error,
+    },
+    /// The remote has no URL configured.
+    #[error("Failed to find remote '{remote}': {source}")]
+    RemoteNotFound {
+        remote: String,
+        #[source]
+        source: git2::Error,
+    },
+    /// Neither the branch's tracking remote nor the fallback remotes are configured.
+    #[error("No usable remote for {head}: it tracks no remote and '{remote}' is not configured")]
+    NoUsableRemote { head: String, remote: String },
+    /// The remote's URL could not be rewritten or does not point at a repository.
+    #[error("Remote '{remote}': {reason}")]
+    InvalidRemoteUrl { remote: String, reason: String },
+    /// The head and base remotes are on different hosts.
+    #[error(
+        "Remote '{base_remote}' is on {base_host} but remote '{head_remote}' is on {head_host}"
+    )]
+    HostMismatch {
+        base_remote: String,
+        base_host: String,
+        head_remote: String,
+        head_host: String,
+    },
+}
+
+/// A GitHub repository identified by its owner and name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoSlug {
     pub owner: String,
     pub repo: String,
-    pub branch: String,
 }
 
-pub fn get_git_info() -> Result<GitInfo, String> {
-    let repo = Repository::open(".").map_err(|e| format!("Failed to open repository: {}", e))?;
+impl RepoSlug {
+    /// Parses a repository from its `owner/repo` full name.
+    pub fn from_full_name(full_name: &str) -> Option<Self> {
+        let (owner, repo) = full_name.split_once('/')?;
+        if owner.is_empty() || repo.is_empty() || repo.contains('/') {
+            return None;
+        }
+        Some(Self {
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for RepoSlug {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.owner, self.repo)
+    }
+}
+
+#[derive(Debug)]
+pub struct GitInfo {
+    pub host: String,
+    /// The port of the host's web server and API, when the remote is an http(s) URL
+    /// naming one.
+    pub port: Option<u16>,
+    /// The checked-out branch, or `None` when HEAD is detached.
+    pub branch: Option<String>,
+    /// The SHA of the checked-out commit, or `None` when there is no local checkout.
+    pub commit: Option<String>,
+    /// The name of the git remote the branch is pushed to, or `None` when there is no
+    /// local checkout.
+    pub remote: Option<String>,
+    /// The repository the branch is pushed to.
+    pub head: RepoSlug,
+    /// The repository pull requests for the branch are opened against.
+    /// This differs from `head` when working from a fork.
+    pub base: RepoSlug,
+}
+
+impl GitInfo {
+    /// Creates the information for a branch of a repository that is not checked out locally.
+    ///
+    /// # Arguments
+    ///
+    /// * `host` - The host serving the repository.
+    /// * `repo` - The repository, used as both the head and the base.
+    /// * `branch` - The branch to watch.
+    pub fn for_remote(host: String, repo: RepoSlug, branch: String) -> Self {
+        Self {
+            host,
+            port: None,
+            branch: Some(branch),
+            commit: None,
+            remote: None,
+            head: repo.clone(),
+            base: repo,
+        }
+    }
+
+    /// Describes what HEAD points at, e.g. `main` or `detached at 1a2b3c4`.
+    pub fn describe_head(&self) -> String {
+        match (&self.branch, &self.commit) {
+            (Some(branch), _) => branch.clone(),
+            (None, Some(commit)) => format!("detached at {}", &commit[..commit.len().min(7)]),
+            (None, None) => "detached HEAD".to_string(),
+        }
+    }
+}
+
+/// Returns the working directory of the repository containing the current directory.
+pub fn repository_root() -> Option<PathBuf> {
+    open_repository(Path::new("."))
+        .ok()?
+        .workdir()
+        .map(|workdir| workdir.to_path_buf())
+}
+
+/// Reads the current branch and the GitHub repositories it belongs to.
+///
+/// # Arguments
+///
+/// * `path` - A directory inside the repository.
+/// * `branch` - The branch to watch instead of the checked-out one.
+/// * `preferred_remote` - The remote to use when the branch does not track one.
+/// * `base_remote` - The remote holding the repository pull requests are opened against.
+///   Defaults to `upstream` if it exists, otherwise to the branch's own remote.
+pub fn get_git_info(
+    path: &Path,
+    branch: Option<&str>,
+    preferred_remote: Option<&str>,
+    base_remote: Option<&str>,
+) -> Result<GitInfo, GitInfoError> {
+    let repo = open_repository(path)?;
+    let config = repo.config().map_err(|source| GitInfoError::Git {
+        context: "Failed to read git config",
+        source,
+    })?;
 
-    let branch = get_current_branch(&repo)?;
-    let (owner, repo_name) = get_owner_and_repo(&repo)?;
+    let (current_branch, commit) = get_current_head(&repo)?;
+    let branch = branch.map(str::to_string).or(current_branch);
+    let remote_name = select_remote(&config, branch.as_deref(), preferred_remote)?;
+    let head = get_owner_and_repo(&config, &remote_name)?;
+
+    let base_remote = match base_remote {
+        Some(name) => Some(name),
+        None if has_url(&config, UPSTREAM_REMOTE) => Some(UPSTREAM_REMOTE),
+        None => None,
+    };
+    let base = match base_remote {
+        Some(name) => {
+            let base = get_owner_and_repo(&config, name)?;
+            if base.host != head.host {
+                return Err(GitInfoError::HostMismatch {
+                    base_remote: name.to_string(),
+                    base_host: base.host,
+                    head_remote: remote_name,
+                    head_host: head.host,
+                });
+            }
+            RepoSlug {
+                owner: base.owner,
+                repo: base.repo,
+            }
+        }
+        None => RepoSlug {
+            owner: head.owner.clone(),
+            repo: head.repo.clone(),
+        },
+    };
 
     Ok(GitInfo {
-        owner,
-        repo: repo_name,
+        host: head.host,
+        // The port of an SSH or git remote says nothing about where the API is.
+        port: head.port.filter(|_| head.http),
         branch,
+        commit: Some(commit),
+        remote: Some(remote_name),
+        head: RepoSlug {
+            owner: head.owner,
+            repo: head.repo,
+        },
+        base,
     })
 }
 
-fn get_current_branch(repo: &Repository) -> Result<String, String> {
-    let head = repo
-        .head()
-        .map_err(|e| format!("Failed to get HEAD: {}", e))?;
-    let branch_name = head.shorthand().ok_or("HEAD is not on a branch")?;
-    Ok(branch_name.to_string())
+/// Opens the repository containing `path`, including linked worktrees.
+fn open_repository(path: &Path) -> Result<Repository, GitInfoError> {
+    Repository::discover(path).map_err(|source| GitInfoError::NotARepository {
+        path: path.to_path_buf(),
+        source,
+    })
 }
 
-fn get_owner_and_repo(repo: &Repository) -> Result<(String, String), String> {
-    let remote = repo
-        .find_remote("origin")
-        .map_err(|e| format!("Failed to find remote 'origin': {}", e))?;
-
-    let url = remote.url().ok_or("Remote 'origin' has no URL")?;
+/// Returns the checked-out branch, if any, and the SHA of the checked-out commit.
+fn get_current_head(repo: &Repository) -> Result<(Option<String>, String), GitInfoError> {
+    let head = repo.head().map_err(|source| GitInfoError::Git {
+        context: "Failed to get HEAD",
+        source,
+    })?;
+    let commit = head.peel_to_commit().map_err(|source| GitInfoError::Git {
+        context: "Failed to resolve HEAD to a commit",
+        source,
+    })?;
 
-    let path = if let Some(stripped) = url.strip_prefix("https://github.com/") {
-        stripped
-    } else if let Some(stripped) = url.strip_prefix("git@github.com:") {
-        stripped
+    // HEAD is detached during rebases and bisects; only the commit is known then.
+    let branch = if head.is_branch() {
+        head.shorthand().map(str::to_string)
     } else {
-        return Err(format!("Unsupported git remote URL format: {}", url));
+        None
     };
 
-    let cleaned_path = path.trim();
-    let repo_path = cleaned_path.trim_end_matches(".git");
+    Ok((branch, commit.id().to_string()))
+}
 
-    let parts: Vec<&str> = repo_path.split('/').collect();
-    if parts.len() == 2 {
-        Ok((parts[0].to_string(), parts[1].to_string()))
-    } else {
-        Err(format!(
-            "Could not parse owner and repo from path: {}",
-            repo_path
-        ))
+/// Chooses the remote `branch` is pushed to, which holds the head of its pull requests.
+///
+/// As with `git push`, `branch.<name>.pushRemote` wins, then `remote.pushDefault`, then
+/// the branch's tracking remote `branch.<name>.remote`, so that a triangular workflow
+/// pulling from `upstream` and pushing to a fork is followed. After those come
+/// `preferred_remote` and finally `origin`. Remotes without a URL, such as the `.` used for
+/// branches tracking a local branch, are skipped. A detached HEAD is pushed nowhere and goes
+/// straight to `preferred_remote`.
+fn select_remote(
+    config: &Config,
+    branch: Option<&str>,
+    preferred_remote: Option<&str>,
+) -> Result<String, GitInfoError> {
+    let push_keys = branch
+        .map(|branch| {
+            vec![
+                format!("branch.{}.pushRemote", branch),
+                "remote.pushDefault".to_string(),
+                format!("branch.{}.remote", branch),
+            ]
+        })
+        .unwrap_or_default();
+    let push_remotes = push_keys
+        .iter()
+        .filter_map(|key| config.get_string(key).ok());
+
+    push_remotes
+        .chain(preferred_remote.map(str::to_string))
+        .chain(Some(DEFAULT_REMOTE.to_string()))
+        .find(|name| has_url(config, name))
+        .ok_or_else(|| GitInfoError::NoUsableRemote {
+            head: branch.map_or("detached HEAD".to_string(), |b| format!("branch '{}'", b)),
+            remote: preferred_remote.unwrap_or(DEFAULT_REMOTE).to_string(),
+        })
+}
+
+/// Returns whether `remote` is configured with a URL.
+fn has_url(config: &Config, remote: &str) -> bool {
+    config.get_string(&format!("remote.{}.url", remote)).is_ok()
+}
+
+fn get_owner_and_repo(config: &Config, remote: &str) -> Result<RemoteUrl, GitInfoError> {
+    // Read the raw URL so that `insteadOf` rewriting is applied exactly once, by us.
+    let url = config
+        .get_string(&format!("remote.{}.url", remote))
+        .map_err(|source| GitInfoError::RemoteNotFound {
+            remote: remote.to_string(),
+            source,
+        })?;
+    let invalid = |reason| GitInfoError::InvalidRemoteUrl {
+        remote: remote.to_string(),
+        reason,
+    };
+    let url = apply_instead_of(config, &url).map_err(invalid)?;
+
+    parse_remote_url(&url).map_err(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    fn config_with(name: &str, contents: &str) -> (TempDir, Config) {
+        let dir = TempDir::new(&format!("git-{}", name));
+        let config = Config::open(&dir.write("config", contents)).unwrap();
+        (dir, config)
+    }
+
+    #[test]
+    fn branches_are_watched_where_they_are_pushed() {
+        let remotes = concat!(
+            "[remote \"origin\"]\n",
+            "\turl = git@github.com:me/repo.git\n",
+            "[remote \"upstream\"]\n",
+            "\turl = git@github.com:owner/repo.git\n",
+            "[remote \"fork\"]\n",
+            "\turl = git@github.com:other/repo.git\n",
+        );
+        let cases = [
+            // Triangular: pull from upstream, push to the fork.
+            (
+                "[branch \"feature\"]\n\tremote = upstream\n\tpushRemote = origin\n",
+                "origin",
+            ),
+            (
+                "[remote]\n\tpushDefault = fork\n[branch \"feature\"]\n\tremote = upstream\n",
+                "fork",
+            ),
+            (
+                "[remote]\n\tpushDefault = fork\n\
+                 [branch \"feature\"]\n\tremote = upstream\n\tpushRemote = origin\n",
+                "origin",
+            ),
+            ("[branch \"feature\"]\n\tremote = upstream\n", "upstream"),
+            // Branches tracking a local branch fall back to the default.
+            ("[branch \"feature\"]\n\tremote = .\n", "origin"),
+            ("", "origin"),
+        ];
+
+        for (i, (branch_config, expected)) in cases.into_iter().enumerate() {
+            let (_dir, config) = config_with(
+                &format!("push-{}", i),
+                &format!("{}{}", remotes, branch_config),
+            );
+            assert_eq!(
+                select_remote(&config, Some("feature"), None).unwrap(),
+                expected,
+                "with {:?}",
+                branch_config
+            );
+        }
+    }
+
+    #[test]
+    fn detached_head_uses_the_preferred_remote() {
+        let (_dir, config) = config_with(
+            "detached",
+            concat!(
+                "[remote \"origin\"]\n",
+                "\turl = git@github.com:me/repo.git\n",
+                "[remote \"fork\"]\n",
+                "\turl = git@github.com:other/repo.git\n",
+                "[remote]\n",
+                "\tpushDefault = origin\n",
+            ),
+        );
+        assert_eq!(select_remote(&config, None, Some("fork")).unwrap(), "fork");
+        assert!(matches!(
+            select_remote(&config, None, Some("missing")),
+            Ok(remote) if remote == "origin"
+        ));
     }
 }
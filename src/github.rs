@@ -1,27 +1,250 @@
-use chrono::{DateTime, Utc};
-use reqwest::Client;
+use chrono::{DateTime, SecondsFormat, Utc};
+use reqwest::header::{ETAG, HeaderMap, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, LINK};
+use reqwest::{Client, RequestBuilder, Response, StatusCode, Url};
 use serde::Deserialize;
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use thiserror::Error;
+use tokio::time;
 
-const GITHUB_API_BASE: &str = "https://api.github.com";
+/// The base URL of the public GitHub API.
+pub const GITHUB_API_BASE: &str = "https://api.github.com";
+
+/// The host serving public GitHub repositories.
+pub const GITHUB_HOST: &str = "github.com";
+
+/// The number of items requested per page of a listing, the maximum the API allows.
+const PER_PAGE: u32 = 100;
+
+/// The maximum number of pages read for one listing, as a guard against endless paging.
+const MAX_PAGES: usize = 50;
+
+/// How long to wait after a `429 Too Many Requests` that says neither when to retry nor
+/// when the limit resets.
+const DEFAULT_RETRY_AFTER_SECS: i64 = 60;
+
+/// Returns the API base URL for repositories hosted on `host`.
+///
+/// Public GitHub is served from `api.github.com`, while GitHub Enterprise Server
+/// exposes its REST API under `/api/v3` on the instance's own hostname and port.
+///
+/// # Arguments
+///
+/// * `host` - The hostname of the git remote, e.g. `github.com` or `github.example.com`.
+/// * `port` - The port of the instance's web server, if not the default.
+pub fn api_base_for_host(host: &str, port: Option<u16>) -> String {
+    match port {
+        _ if host.eq_ignore_ascii_case(GITHUB_HOST) => GITHUB_API_BASE.to_string(),
+        Some(port) => format!("https://{}:{}/api/v3", host, port),
+        None => format!("https://{}/api/v3", host),
+    }
+}
+
+/// An error talking to the GitHub API.
+#[derive(Debug, Error)]
+pub enum GitHubError {
+    /// The API could not be reached, or did not answer in time, even after retrying.
+    #[error("Network error: {0}")]
+    Network(#[source] reqwest::Error),
+    /// The token is missing, invalid or expired (`401 Unauthorized`).
+    #[error("API Error ({status}): {body}")]
+    Unauthorized { status: StatusCode, body: String },
+    /// The resource does not exist, or the token may not see it (`404 Not Found`).
+    #[error("API Error ({status}): {body}")]
+    NotFound { status: StatusCode, body: String },
+    /// A rate limit was exceeded. Requests fail without being sent until `until`.
+    #[error("Rate limit exceeded, paused until {}", until.format("%H:%M:%S UTC"))]
+    RateLimited {
+        /// The status of the refused request, or `None` if no request was sent.
+        status: Option<StatusCode>,
+        until: DateTime<Utc>,
+    },
+    /// GitHub failed to handle the request (`5xx`), even after retrying.
+    #[error("API Error ({status}): {body}")]
+    Server { status: StatusCode, body: String },
+    /// Any other unsuccessful response.
+    #[error("API Error ({status}): {body}")]
+    Api { status: StatusCode, body: String },
+    /// The response body did not have the expected shape.
+    #[error("JSON decode error: {source} on URL: {url}")]
+    Decode {
+        url: String,
+        #[source]
+        source: reqwest::Error,
+    },
+    /// A `Link` header pointed somewhere other than the API the client is bound to.
+    #[error("Refusing to follow next page outside the API: {0}")]
+    ForeignNextPage(String),
+}
+
+impl GitHubError {
+    /// Classifies an unsuccessful response that is not about the rate limit.
+    ///
+    /// # Arguments
+    ///
+    /// * `status` - The status of the response.
+    /// * `body` - The body of the response, usually a JSON message.
+    fn from_response(status: StatusCode, body: String) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED => GitHubError::Unauthorized { status, body },
+            StatusCode::NOT_FOUND => GitHubError::NotFound { status, body },
+            status if status.is_server_error() => GitHubError::Server { status, body },
+            status => GitHubError::Api { status, body },
+        }
+    }
+
+    /// Returns the HTTP status of the response that caused the error, if there was one.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            GitHubError::Unauthorized { status, .. }
+            | GitHubError::NotFound { status, .. }
+            | GitHubError::Server { status, .. }
+            | GitHubError::Api { status, .. } => Some(*status),
+            GitHubError::RateLimited { status, .. } => *status,
+            GitHubError::Network(_)
+            | GitHubError::Decode { .. }
+            | GitHubError::ForeignNextPage(_) => None,
+        }
+    }
+}
 
 /// A client for interacting with the GitHub API.
 #[derive(Debug)]
 pub struct GitHubClient {
     /// The HTTP client used to make requests to the GitHub API.
     client: Client,
+    /// The base URL of the API, without a trailing slash.
+    api_base: String,
     /// The owner of the repository.
     owner: String,
     /// The name of the repository.
     repo: String,
     /// The personal access token used to authenticate with the GitHub API.
     token: String,
+    /// The last successful response for each listing, keyed by [`cache_key`], for
+    /// conditional requests.
+    cache: Mutex<HashMap<String, CachedResponse>>,
+    /// What the API last said about the rate limit of the token.
+    rate_limit: SharedRateLimit,
+    /// The requests counted against the rate limit since [`Self::take_request_count`].
+    requests: AtomicU32,
+    /// How requests are timed out and retried.
+    retry_policy: RetryPolicy,
+    /// Whether the latest request failed because the API could not be reached.
+    offline: AtomicBool,
+}
+
+/// How requests are timed out and retried after transient failures.
+///
+/// Connection errors, timeouts and `502`, `503` and `504` responses are retried with
+/// jittered exponential backoff; other failures are returned straight away.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// How long a single attempt may take before it is abandoned.
+    pub timeout: Duration,
+    /// How many times a request is retried after the first attempt.
+    pub retries: u32,
+    /// The delay before the first retry. Each further retry waits about twice as long.
+    pub base_delay: Duration,
+    /// The longest delay between two attempts.
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(15),
+            retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(8),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Returns the delay before the given retry, counting from 0.
+    ///
+    /// Half of the delay is random, so that clients which failed together do not all
+    /// retry at the same moment.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let backoff = self
+            .base_delay
+            .saturating_mul(1 << retry.min(16))
+            .min(self.max_delay);
+        backoff / 2 + backoff.mul_f64(fastrand::f64() / 2.0)
+    }
+}
+
+/// The rate-limit budget of the token, as reported by the `X-RateLimit-*` headers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    /// The number of requests allowed per window.
+    pub limit: u32,
+    /// The number of requests left in the current window.
+    pub remaining: u32,
+    /// When the current window ends and the budget is refilled.
+    pub reset: DateTime<Utc>,
+}
+
+/// The rate-limit information gathered from responses.
+#[derive(Debug, Default)]
+struct RateLimitState {
+    /// The budget reported by the latest response.
+    latest: Option<RateLimit>,
+    /// When requests may be made again after the API refused one for exceeding a limit.
+    blocked_until: Option<DateTime<Utc>>,
+    /// The number of clients drawing on the budget.
+    clients: usize,
+}
+
+/// The rate-limit budget of a token on an API, shared by the clients that use it.
+///
+/// GitHub counts requests per token, so clients for several repositories on one host
+/// drain the same budget, and a client refused for exceeding it pauses all of them.
+#[derive(Debug, Clone, Default)]
+pub struct SharedRateLimit(Arc<Mutex<RateLimitState>>);
+
+impl SharedRateLimit {
+    /// Returns the number of clients currently drawing on the budget.
+    pub fn clients(&self) -> usize {
+        self.0.lock().unwrap().clients
+    }
+}
+
+/// A response kept to revalidate with `If-None-Match` or `If-Modified-Since`.
+///
+/// The deserialized value is kept rather than the body, so that a `304 Not Modified`
+/// skips deserialization entirely.
+#[derive(Debug)]
+struct CachedResponse {
+    /// The URL the response is for. Only a request for the same URL may revalidate it.
+    url: String,
+    /// The `ETag` header of the response.
+    etag: Option<String>,
+    /// The `Last-Modified` header of the response.
+    last_modified: Option<String>,
+    /// The URL of the next page, from the `Link` header.
+    next: Option<String>,
+    /// The deserialized body.
+    value: Arc<dyn Any + Send + Sync>,
 }
 
 /// Represents a single workflow run in GitHub Actions.
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone)]
 pub struct WorkflowRun {
     /// The unique identifier for the workflow run.
     pub id: i64,
+    /// The name of the workflow run, usually the workflow's name.
+    pub name: Option<String>,
+    /// The URL of the workflow run on GitHub.
+    pub html_url: String,
+    /// The branch the workflow run was triggered for.
+    pub head_branch: Option<String>,
+    /// The user who triggered the workflow run.
+    pub actor: Option<User>,
     /// The current status of the workflow run.
     pub status: WorkflowRunStatus,
     /// The conclusion of the workflow run.
@@ -30,11 +253,14 @@ pub struct WorkflowRun {
     pub created_at: DateTime<Utc>,
     /// The timestamp of when the workflow run was last updated.
     pub updated_at: DateTime<Utc>,
+    /// The repository the commit that triggered the run was pushed to.
+    /// For pull requests from forks this is the fork.
+    pub head_repository: Option<Repository>,
 }
 
 /// Represents the status of a workflow run.
 /// See: https://docs.github.com/en/rest/actions/workflow-runs?apiVersion=2022-11-28#get-a-workflow-run
-#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum WorkflowRunStatus {
     Completed,
@@ -55,7 +281,7 @@ pub enum WorkflowRunStatus {
 
 /// Represents the conclusion of a workflow run.
 /// See: https://docs.github.com/en/rest/actions/workflow-runs?apiVersion=2022-11-28#get-a-workflow-run
-#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum WorkflowRunConclusion {
     Success,
@@ -69,14 +295,35 @@ pub enum WorkflowRunConclusion {
 }
 
 /// A list of workflow runs.
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone)]
 pub struct ListWorkflowRuns {
     /// A vector containing the workflow runs.
     pub workflow_runs: Vec<WorkflowRun>,
 }
 
+/// Represents a GitHub user, as embedded in other resources.
+#[derive(Deserialize, Debug, Clone)]
+pub struct User {
+    /// The login name of the user.
+    pub login: String,
+}
+
+/// Represents a repository on GitHub.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Repository {
+    /// The name of the repository.
+    pub name: String,
+    /// The full name of the repository, in `owner/name` form.
+    pub full_name: String,
+    /// Whether the repository is a fork.
+    #[serde(default)]
+    pub fork: bool,
+    /// The repository this one was forked from, if it is a fork.
+    pub parent: Option<Box<Repository>>,
+}
+
 /// Represents a pull request on GitHub.
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone)]
 pub struct PullRequest {
     /// The unique identifier for the pull request.
     pub id: i64,
@@ -84,8 +331,13 @@ pub struct PullRequest {
     pub number: u64,
     /// The title of the pull request.
     pub title: String,
+    /// The URL of the pull request on GitHub.
+    pub html_url: String,
     /// Whether the pull request has been merged.
     pub merged: Option<bool>,
+    /// The user who merged the pull request.
+    /// Only included when fetching a single pull request.
+    pub merged_by: Option<User>,
     /// The timestamp of when the pull request was merged.
     pub merged_at: Option<DateTime<Utc>>,
     /// The timestamp of when the pull request was created.
@@ -95,23 +347,33 @@ pub struct PullRequest {
 }
 
 /// Represents a comment on a pull request.
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone)]
 pub struct Comment {
     /// The unique identifier for the comment.
     pub id: i64,
     /// The body of the comment.
     pub body: String,
+    /// The URL of the comment on GitHub.
+    pub html_url: String,
+    /// The author of the comment.
+    pub user: Option<User>,
     /// The timestamp of when the comment was created.
     pub created_at: DateTime<Utc>,
 }
 
 /// Represents a review on a pull request.
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone)]
 pub struct Review {
     /// The unique identifier for the review.
     pub id: i64,
     /// The current state of the review.
     pub state: ReviewState,
+    /// The summary comment left with the review.
+    pub body: Option<String>,
+    /// The URL of the review on GitHub.
+    pub html_url: String,
+    /// The author of the review.
+    pub user: Option<User>,
     /// The timestamp of when the review was submitted.
     pub submitted_at: DateTime<Utc>,
 }
@@ -119,7 +381,7 @@ pub struct Review {
 /// Represents the state of a pull request review.
 /// The state can be one of several predefined values.
 /// See: https://docs.github.com/en/rest/pulls/reviews?apiVersion=2022-11-28#list-reviews-for-a-pull-request
-#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum ReviewState {
     /// The reviewer has approved the pull request.
@@ -135,53 +397,374 @@ pub enum ReviewState {
 }
 
 impl GitHubClient {
-    /// Creates a new `GitHubClient`.
+    /// Creates a new `GitHubClient` with the default retry policy.
     ///
     /// # Arguments
     ///
+    /// * `api_base` - The base URL of the API, e.g. `https://api.github.com`.
     /// * `owner` - The owner of the repository.
     /// * `repo` - The name of the repository.
     /// * `token` - The personal access token used to authenticate with the GitHub API.
-    pub fn new(owner: String, repo: String, token: String) -> Self {
+    pub fn new(api_base: String, owner: String, repo: String, token: String) -> Self {
+        Self::with_retry_policy(api_base, owner, repo, token, RetryPolicy::default())
+    }
+
+    /// Creates a new `GitHubClient` that times out and retries requests as given.
+    ///
+    /// # Arguments
+    ///
+    /// * `api_base` - The base URL of the API, e.g. `https://api.github.com`.
+    /// * `owner` - The owner of the repository.
+    /// * `repo` - The name of the repository.
+    /// * `token` - The personal access token used to authenticate with the GitHub API.
+    /// * `retry_policy` - How requests are timed out and retried.
+    pub fn with_retry_policy(
+        api_base: String,
+        owner: String,
+        repo: String,
+        token: String,
+        retry_policy: RetryPolicy,
+    ) -> Self {
+        Self::with_shared_rate_limit(
+            api_base,
+            owner,
+            repo,
+            token,
+            retry_policy,
+            SharedRateLimit::default(),
+        )
+    }
+
+    /// Creates a new `GitHubClient` that draws on a rate-limit budget shared with other
+    /// clients using the same token.
+    ///
+    /// # Arguments
+    ///
+    /// * `api_base` - The base URL of the API, e.g. `https://api.github.com`.
+    /// * `owner` - The owner of the repository.
+    /// * `repo` - The name of the repository.
+    /// * `token` - The personal access token used to authenticate with the GitHub API.
+    /// * `retry_policy` - How requests are timed out and retried.
+    /// * `rate_limit` - The budget of `token` on the API.
+    pub fn with_shared_rate_limit(
+        api_base: String,
+        owner: String,
+        repo: String,
+        token: String,
+        retry_policy: RetryPolicy,
+        rate_limit: SharedRateLimit,
+    ) -> Self {
+        rate_limit.0.lock().unwrap().clients += 1;
         Self {
-            client: Client::new(),
+            client: Client::builder()
+                .timeout(retry_policy.timeout)
+                .build()
+                .unwrap_or_default(),
+            api_base: api_base.trim_end_matches('/').to_string(),
             owner,
             repo,
             token,
+            cache: Mutex::new(HashMap::new()),
+            rate_limit,
+            requests: AtomicU32::new(0),
+            retry_policy,
+            offline: AtomicBool::new(false),
         }
     }
 
+    /// Returns whether the latest request failed because the API could not be reached,
+    /// even after retrying.
+    pub fn is_offline(&self) -> bool {
+        self.offline.load(Ordering::Relaxed)
+    }
+
+    /// Returns the rate-limit budget reported by the latest response, if any.
+    ///
+    /// The latest response may have been to another client sharing the budget.
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        self.rate_limit.0.lock().unwrap().latest
+    }
+
+    /// Returns the number of clients drawing on this client's rate-limit budget, itself
+    /// included.
+    pub fn sharing_clients(&self) -> usize {
+        self.rate_limit.clients()
+    }
+
+    /// Returns the number of requests counted against the rate limit since the last call,
+    /// i.e. those not answered with `304 Not Modified`.
+    pub fn take_request_count(&self) -> u32 {
+        self.requests.swap(0, Ordering::Relaxed)
+    }
+
+    /// Returns when requests may be made again, if the rate limit is currently exhausted.
+    ///
+    /// While this is `Some`, requests fail without being sent.
+    pub fn rate_limited_until(&self) -> Option<DateTime<Utc>> {
+        let state = self.rate_limit.0.lock().unwrap();
+        let now = Utc::now();
+        let exhausted = state
+            .latest
+            .filter(|rate_limit| rate_limit.remaining == 0)
+            .map(|rate_limit| rate_limit.reset);
+        state
+            .blocked_until
+            .into_iter()
+            .chain(exhausted)
+            .filter(|until| *until > now)
+            .max()
+    }
+
     /// Sends a GET request to the GitHub API and deserializes the response.
     ///
     /// # Arguments
     ///
     /// * `url` - The URL to send the GET request to.
-    async fn get<T: for<'de> Deserialize<'de>>(&self, url: &str) -> Result<T, String> {
-        let response = self
+    async fn get<T>(&self, url: &str) -> Result<T, GitHubError>
+    where
+        T: for<'de> Deserialize<'de> + Clone + Send + Sync + 'static,
+    {
+        self.get_page(url).await.map(|(value, _)| value)
+    }
+
+    /// Sends a GET request to the GitHub API and deserializes the response, also returning
+    /// the URL of the next page from the `Link` header, if there is one.
+    ///
+    /// Requests for a URL fetched before are conditional. When the API answers
+    /// `304 Not Modified`, which does not count against the rate limit, the value from the
+    /// earlier response is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL to send the GET request to.
+    async fn get_page<T>(&self, url: &str) -> Result<(T, Option<String>), GitHubError>
+    where
+        T: for<'de> Deserialize<'de> + Clone + Send + Sync + 'static,
+    {
+        if let Some(until) = self.rate_limited_until() {
+            return Err(GitHubError::RateLimited {
+                status: None,
+                until,
+            });
+        }
+
+        let mut request = self
             .client
             .get(url)
             .header("Authorization", format!("Bearer {}", self.token))
             .header("User-Agent", "reposouls-app")
-            .header("Accept", "application/vnd.github.v3+json")
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
+            .header("Accept", "application/vnd.github.v3+json");
+        let key = cache_key(url);
+        if let Some(cached) = self.cache.lock().unwrap().get(&key)
+            && cached.url == url
+        {
+            if let Some(etag) = &cached.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &cached.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+        let response = self.send(request).await?;
+        if response.status() != StatusCode::NOT_MODIFIED {
+            self.requests.fetch_add(1, Ordering::Relaxed);
+        }
+        self.record_rate_limit(response.status(), response.headers())?;
+
+        if response.status() == StatusCode::NOT_MODIFIED
+            && let Some(cached) = self.cache.lock().unwrap().get(&key)
+            && cached.url == url
+            && let Some(value) = cached.value.downcast_ref::<T>()
+        {
+            return Ok((value.clone(), cached.next.clone()));
+        }
 
         if response.status().is_success() {
-            response
+            let headers = response.headers();
+            let header = |name| {
+                headers
+                    .get(name)
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_string)
+            };
+            let etag = header(ETAG);
+            let last_modified = header(LAST_MODIFIED);
+            let next = next_page_url(headers);
+            let value = response
                 .json::<T>()
                 .await
-                .map_err(|e| format!("JSON decode error: {} on URL: {}", e, url))
+                .map_err(|source| GitHubError::Decode {
+                    url: url.to_string(),
+                    source,
+                })?;
+            if etag.is_some() || last_modified.is_some() {
+                self.cache.lock().unwrap().insert(
+                    key,
+                    CachedResponse {
+                        url: url.to_string(),
+                        etag,
+                        last_modified,
+                        next: next.clone(),
+                        value: Arc::new(value.clone()),
+                    },
+                );
+            }
+            Ok((value, next))
         } else {
             let status = response.status();
             let text = response
                 .text()
                 .await
                 .unwrap_or_else(|_| "Could not read error body".to_string());
-            Err(format!("API Error ({}): {}", status, text))
+            Err(GitHubError::from_response(status, text))
         }
     }
 
+    /// Sends a request, retrying transient failures according to the retry policy.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The request to send. It must not have a streaming body.
+    async fn send(&self, request: RequestBuilder) -> Result<Response, GitHubError> {
+        let mut retry = 0;
+        loop {
+            let attempt = request
+                .try_clone()
+                .expect("requests without a streaming body can be cloned");
+            let result = attempt.send().await;
+            let unreachable = matches!(&result, Err(e) if e.is_connect() || e.is_timeout());
+            let transient = unreachable
+                || matches!(&result, Ok(response) if matches!(
+                    response.status(),
+                    StatusCode::BAD_GATEWAY
+                        | StatusCode::SERVICE_UNAVAILABLE
+                        | StatusCode::GATEWAY_TIMEOUT
+                ));
+            if !transient || retry >= self.retry_policy.retries {
+                self.offline.store(unreachable, Ordering::Relaxed);
+                return result.map_err(GitHubError::Network);
+            }
+            time::sleep(self.retry_policy.delay(retry)).await;
+            retry += 1;
+        }
+    }
+
+    /// Updates the rate-limit state from a response's headers.
+    ///
+    /// Returns an error if the response is a refusal for exceeding the primary or a
+    /// secondary rate limit, after which requests are paused until the limit allows them.
+    fn record_rate_limit(
+        &self,
+        status: StatusCode,
+        headers: &HeaderMap,
+    ) -> Result<(), GitHubError> {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse::<i64>().ok())
+        };
+        let now = Utc::now();
+        let mut state = self.rate_limit.0.lock().unwrap();
+
+        if let (Some(limit), Some(remaining), Some(reset)) = (
+            header("x-ratelimit-limit"),
+            header("x-ratelimit-remaining"),
+            header("x-ratelimit-reset"),
+        ) && let Some(reset) = DateTime::from_timestamp(reset, 0)
+        {
+            state.latest = Some(RateLimit {
+                limit: limit.try_into().unwrap_or(0),
+                remaining: remaining.try_into().unwrap_or(0),
+                reset,
+            });
+        }
+
+        if status != StatusCode::FORBIDDEN && status != StatusCode::TOO_MANY_REQUESTS {
+            return Ok(());
+        }
+        let retry_after = header("retry-after").map(|secs| now + chrono::Duration::seconds(secs));
+        let exhausted = state
+            .latest
+            .filter(|rate_limit| rate_limit.remaining == 0)
+            .map(|rate_limit| rate_limit.reset);
+        let until = match (retry_after.or(exhausted), status) {
+            (Some(until), _) => until,
+            (None, StatusCode::TOO_MANY_REQUESTS) => {
+                now + chrono::Duration::seconds(DEFAULT_RETRY_AFTER_SECS)
+            }
+            // A 403 without rate-limit information is a genuine permission error.
+            (None, _) => return Ok(()),
+        };
+        state.blocked_until = Some(until);
+        Err(GitHubError::RateLimited {
+            status: Some(status),
+            until,
+        })
+    }
+
+    /// Whether a URL points into the API the client is bound to.
+    ///
+    /// The scheme, host and port must match and the path must lie below the base path, so
+    /// neither `https://api.github.com.example.net` nor `https://api.github.com@example.net`
+    /// passes for `https://api.github.com`.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL to check.
+    fn is_within_api(&self, url: &str) -> bool {
+        let (Ok(base), Ok(url)) = (Url::parse(&self.api_base), Url::parse(url)) else {
+            return false;
+        };
+        let base_path = base.path().trim_end_matches('/');
+        url.scheme() == base.scheme()
+            && url.host() == base.host()
+            && url.port_or_known_default() == base.port_or_known_default()
+            && url.username().is_empty()
+            && url.password().is_none()
+            && url
+                .path()
+                .strip_prefix(base_path)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    }
+
+    /// Reads every page of a listing, following the `Link: rel="next"` headers.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the first page.
+    /// * `items` - Extracts the items from a deserialized page.
+    async fn get_all<P, T>(
+        &self,
+        url: &str,
+        items: impl Fn(P) -> Vec<T>,
+    ) -> Result<Vec<T>, GitHubError>
+    where
+        P: for<'de> Deserialize<'de> + Clone + Send + Sync + 'static,
+    {
+        let mut all = Vec::new();
+        let mut next = Some(url.to_string());
+        for _ in 0..MAX_PAGES {
+            let Some(url) = next.take() else {
+                return Ok(all);
+            };
+            // Never send the token anywhere but the API the client is bound to.
+            if !self.is_within_api(&url) {
+                return Err(GitHubError::ForeignNextPage(url));
+            }
+            let (page, next_url) = self.get_page::<P>(&url).await?;
+            all.extend(items(page));
+            next = next_url;
+        }
+        if next.is_some() {
+            eprintln!(
+                "Stopped reading {} after {} pages; later items are skipped",
+                url, MAX_PAGES
+            );
+        }
+        Ok(all)
+    }
+
     /// Gets workflow runs for a specific branch created after a given time.
     ///
     /// # Arguments
@@ -192,43 +775,87 @@ impl GitHubClient {
         &self,
         branch: &str,
         start_time: DateTime<Utc>,
-    ) -> Result<Vec<WorkflowRun>, String> {
-        let created_filter = start_time.to_rfc3339();
+    ) -> Result<Vec<WorkflowRun>, GitHubError> {
+        let url = format!(
+            "{}/repos/{}/{}/actions/runs?branch={}&created=>{}&per_page={}",
+            self.api_base,
+            self.owner,
+            self.repo,
+            branch,
+            format_time(start_time),
+            PER_PAGE
+        );
+        self.get_all(&url, |page: ListWorkflowRuns| page.workflow_runs)
+            .await
+    }
+
+    /// Gets workflow runs for a specific commit created after a given time.
+    ///
+    /// # Arguments
+    ///
+    /// * `sha` - The SHA of the commit to get workflow runs for.
+    /// * `start_time` - The time to fetch workflow runs created after.
+    pub async fn get_workflow_runs_for_commit(
+        &self,
+        sha: &str,
+        start_time: DateTime<Utc>,
+    ) -> Result<Vec<WorkflowRun>, GitHubError> {
         let url = format!(
-            "{}/repos/{}/{}/actions/runs?branch={}&created=>{}",
-            GITHUB_API_BASE, self.owner, self.repo, branch, created_filter
+            "{}/repos/{}/{}/actions/runs?head_sha={}&created=>{}&per_page={}",
+            self.api_base,
+            self.owner,
+            self.repo,
+            sha,
+            format_time(start_time),
+            PER_PAGE
         );
-        let response: ListWorkflowRuns = self.get(&url).await?;
-        Ok(response.workflow_runs)
+        self.get_all(&url, |page: ListWorkflowRuns| page.workflow_runs)
+            .await
     }
 
     /// Gets the latest pull request for a specific branch.
     ///
     /// # Arguments
     ///
+    /// * `head_owner` - The owner of the repository the branch is pushed to.
+    ///   This differs from the client's owner when the branch lives on a fork.
     /// * `branch` - The name of the branch to get the pull request for.
-    pub async fn get_pr_for_branch(&self, branch: &str) -> Result<Option<PullRequest>, String> {
-        let head = format!("{}:{}", self.owner, branch);
+    pub async fn get_pr_for_branch(
+        &self,
+        head_owner: &str,
+        branch: &str,
+    ) -> Result<Option<PullRequest>, GitHubError> {
+        let head = format!("{}:{}", head_owner, branch);
         let url = format!(
             "{}/repos/{}/{}/pulls?state=all&sort=created&direction=desc&head={}&per_page=1",
-            GITHUB_API_BASE, self.owner, self.repo, head
+            self.api_base, self.owner, self.repo, head
         );
         let mut prs: Vec<PullRequest> = self.get(&url).await?;
         Ok(prs.pop())
     }
 
-    /// Gets all comments for a specific pull request.
+    /// Gets the comments for a specific pull request updated at or after a given time.
     /// These are "Issue comments" that appear in the conversation tab.
     ///
     /// # Arguments
     ///
     /// * `pr_number` - The number of the pull request.
-    pub async fn get_pr_comments(&self, pr_number: u64) -> Result<Vec<Comment>, String> {
+    /// * `since` - The time to fetch comments updated at or after.
+    pub async fn get_pr_comments(
+        &self,
+        pr_number: u64,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<Comment>, GitHubError> {
         let url = format!(
-            "{}/repos/{}/{}/issues/{}/comments",
-            GITHUB_API_BASE, self.owner, self.repo, pr_number
+            "{}/repos/{}/{}/issues/{}/comments?since={}&per_page={}",
+            self.api_base,
+            self.owner,
+            self.repo,
+            pr_number,
+            format_time(since),
+            PER_PAGE
         );
-        self.get(&url).await
+        self.get_all(&url, |page: Vec<Comment>| page).await
     }
 
     /// Gets all reviews for a specific pull request.
@@ -237,11 +864,17 @@ impl GitHubClient {
     /// # Arguments
     ///
     /// * `pr_number` - The number of the pull request.
-    pub async fn get_pr_reviews(&self, pr_number: u64) -> Result<Vec<Review>, String> {
+    pub async fn get_pr_reviews(&self, pr_number: u64) -> Result<Vec<Review>, GitHubError> {
         let url = format!(
-            "{}/repos/{}/{}/pulls/{}/reviews",
-            GITHUB_API_BASE, self.owner, self.repo, pr_number
+            "{}/repos/{}/{}/pulls/{}/reviews?per_page={}",
+            self.api_base, self.owner, self.repo, pr_number, PER_PAGE
         );
+        self.get_all(&url, |page: Vec<Review>| page).await
+    }
+
+    /// Gets the repository the client is bound to.
+    pub async fn get_repository(&self) -> Result<Repository, GitHubError> {
+        let url = format!("{}/repos/{}/{}", self.api_base, self.owner, self.repo);
         self.get(&url).await
     }
 
@@ -250,11 +883,58 @@ impl GitHubClient {
     /// # Arguments
     ///
     /// * `pr_number` - The number of the pull request.
-    pub async fn get_pr_details(&self, pr_number: u64) -> Result<PullRequest, String> {
+    pub async fn get_pr_details(&self, pr_number: u64) -> Result<PullRequest, GitHubError> {
         let url = format!(
             "{}/repos/{}/{}/pulls/{}",
-            GITHUB_API_BASE, self.owner, self.repo, pr_number
+            self.api_base, self.owner, self.repo, pr_number
         );
         self.get(&url).await
     }
 }
+
+impl Drop for GitHubClient {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.rate_limit.0.lock() {
+            state.clients -= 1;
+        }
+    }
+}
+
+/// Returns the key a response to `url` is cached under: the URL without the `created` and
+/// `since` cursors, so that moving a cursor replaces the listing's entry rather than adding
+/// another one.
+fn cache_key(url: &str) -> String {
+    let Ok(mut parsed) = Url::parse(url) else {
+        return url.to_string();
+    };
+    let query: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(name, _)| name != "created" && name != "since")
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+    parsed.query_pairs_mut().clear().extend_pairs(query);
+    parsed.into()
+}
+
+/// Formats a time for a query parameter, in the `Z` form that needs no URL encoding.
+fn format_time(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Returns the URL of the next page from a `Link` header such as
+/// `<https://api.github.com/...&page=2>; rel="next", <...>; rel="last"`.
+fn next_page_url(headers: &HeaderMap) -> Option<String> {
+    let link = headers.get(LINK)?.to_str().ok()?;
+    link.split(',').find_map(|entry| {
+        let (url, params) = entry.split_once(';')?;
+        params
+            .split(';')
+            .any(|param| param.trim() == "rel=\"next\"")
+            .then(|| {
+                url.trim()
+                    .trim_start_matches('<')
+                    .trim_end_matches('>')
+                    .to_string()
+            })
+    })
+}
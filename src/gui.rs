@@ -1,17 +1,56 @@
+use crate::config::{Config, DismissPolicy, GuiConfig, PreemptPolicy};
 use crate::events::NotificationEvent;
+use crate::keys::KeyWatcher;
+use crate::queue::{NotificationQueue, QueuedNotification};
+use crate::status::Status;
 use eframe::{
     NativeOptions,
-    egui::{self, Color32, ColorImage, TextureHandle, TextureOptions, ViewportBuilder},
+    egui::{
+        self, Color32, ColorImage, FontData, FontDefinitions, FontFamily, FontId, RichText,
+        TextureHandle, TextureOptions, ViewportBuilder,
+    },
 };
 use image;
 use rust_embed::RustEmbed;
+use std::borrow::Cow;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
 use std::sync::mpsc::Receiver;
 
 #[derive(RustEmbed)]
 #[folder = "images/"]
 struct Assets;
 
-pub fn run_gui(image_receiver: Receiver<NotificationEvent>) -> Result<(), eframe::Error> {
+/// Serif fonts tried, in order, when no subtitle font is configured.
+const SERIF_FONT_CANDIDATES: &[&str] = &[
+    "/usr/share/fonts/truetype/dejavu/DejaVuSerif.ttf",
+    "/usr/share/fonts/dejavu/DejaVuSerif.ttf",
+    "/usr/share/fonts/truetype/liberation/LiberationSerif-Regular.ttf",
+    "/usr/share/fonts/liberation/LiberationSerif-Regular.ttf",
+    "/System/Library/Fonts/Supplemental/Times New Roman.ttf",
+    "/Library/Fonts/Times New Roman.ttf",
+    "C:\\Windows\\Fonts\\times.ttf",
+];
+
+/// The name the subtitle font is registered under.
+const SUBTITLE_FONT_NAME: &str = "reposouls-subtitle";
+
+/// The colour of the subtitle text, a parchment white matching the banners.
+const SUBTITLE_COLOR: (u8, u8, u8) = (235, 225, 200);
+
+/// The colour of the indicator shown while GitHub cannot be reached, a faded version of
+/// the subtitle colour.
+const OFFLINE_COLOR: Color32 = Color32::from_rgba_premultiplied(118, 113, 100, 128);
+
+/// The colour of the error shown when the event checker stops, a dark blood red.
+const ERROR_COLOR: (u8, u8, u8) = (170, 30, 25);
+
+pub fn run_gui(
+    image_receiver: Receiver<NotificationEvent>,
+    config: Config,
+    status: Arc<Status>,
+) -> Result<(), eframe::Error> {
     let options = NativeOptions {
         viewport: ViewportBuilder::default()
             .with_transparent(true)
@@ -25,49 +64,350 @@ pub fn run_gui(image_receiver: Receiver<NotificationEvent>) -> Result<(), eframe
     eframe::run_native(
         "Reposouls Notification",
         options,
-        Box::new(move |cc| Box::new(App::new(cc, image_receiver))),
+        Box::new(move |cc| Box::new(App::new(cc, image_receiver, config, status))),
     )
 }
 
-#[derive(PartialEq)]
+/// How long a banner takes to fade in or out, in seconds.
+const FADE_SECS: f64 = 0.5;
+
+/// How long the error that stopped the event checker is shown before the overlay closes,
+/// in seconds.
+const FATAL_DISPLAY_SECS: f64 = 10.0;
+
+#[derive(Debug, PartialEq)]
 enum AppState {
     Idle,
     FadingIn,
     Displaying,
     FadingOut,
+    /// The event checker stopped; its error is shown until the overlay closes.
+    Failed,
+}
+
+/// The input seen during one frame, as far as dismissing banners is concerned.
+#[derive(Debug, Default, Clone, Copy)]
+struct FrameInput {
+    /// Whether the mouse was moved, clicked or scrolled over the overlay, or a key was
+    /// pressed in any window.
+    activity: bool,
+    /// Whether a mouse button was pressed over the banner.
+    clicked: bool,
+}
+
+impl FrameInput {
+    /// Reads the mouse input egui saw during the frame.
+    ///
+    /// Key presses go to the focused window rather than the overlay, so the caller adds
+    /// those seen by a [`KeyWatcher`].
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The overlay's context.
+    /// * `banner` - Where the banner was drawn, if it is on screen.
+    fn read(ctx: &egui::Context, banner: Option<egui::Rect>) -> Self {
+        ctx.input(|i| {
+            let pressed = i.pointer.any_pressed();
+            let clicked = pressed
+                && banner
+                    .zip(i.pointer.interact_pos())
+                    .is_some_and(|(banner, pos)| banner.contains(pos));
+            let activity = pressed
+                || i.events.iter().any(|event| {
+                    matches!(
+                        event,
+                        egui::Event::PointerMoved(_) | egui::Event::MouseWheel { .. }
+                    )
+                });
+            FrameInput { activity, clicked }
+        })
+    }
 }
 
 struct App {
     image_receiver: Receiver<NotificationEvent>,
+    config: Config,
+    status: Arc<Status>,
+    queue: NotificationQueue,
+    /// The notification being shown, until it starts fading out.
+    current: Option<QueuedNotification>,
     texture: Option<TextureHandle>,
+    subtitle: Option<String>,
+    subtitle_font: FontId,
+    /// The error that stopped the event checker, once the GUI has noticed it.
+    fatal_error: Option<String>,
     state: AppState,
     animation_time: f64,
+    /// Where the banner was drawn in the latest frame, for telling clicks on it apart.
+    banner_rect: Option<egui::Rect>,
+    /// Notices key presses, which the overlay does not receive, for the activity policy.
+    keys: KeyWatcher,
 }
 
 impl App {
-    fn new(_cc: &eframe::CreationContext<'_>, image_receiver: Receiver<NotificationEvent>) -> Self {
+    fn new(
+        cc: &eframe::CreationContext<'_>,
+        image_receiver: Receiver<NotificationEvent>,
+        config: Config,
+        status: Arc<Status>,
+    ) -> Self {
+        let family = App::install_subtitle_font(&cc.egui_ctx, &config.gui);
+        let subtitle_font = FontId::new(config.gui.font_size, family);
+        App::with_font(
+            image_receiver,
+            config,
+            status,
+            subtitle_font,
+            KeyWatcher::new(),
+        )
+    }
+
+    fn with_font(
+        image_receiver: Receiver<NotificationEvent>,
+        config: Config,
+        status: Arc<Status>,
+        subtitle_font: FontId,
+        keys: KeyWatcher,
+    ) -> Self {
         Self {
             image_receiver,
+            status,
+            queue: NotificationQueue::new(config.queue.clone()),
+            config,
+            current: None,
             texture: None,
+            subtitle: None,
+            subtitle_font,
+            fatal_error: None,
             state: AppState::Idle,
             animation_time: 0.0,
+            banner_rect: None,
+            keys,
         }
     }
 
-    fn get_image_path_for_event(event: &NotificationEvent) -> &'static str {
-        match event {
-            NotificationEvent::CiSuccess => "CI PIPELINE GREENED.png",
-            NotificationEvent::CiFailure => "CI PIPELINE FAILED.png",
-            NotificationEvent::PrApproved => "PR APPROVAL GRANTED.png",
-            NotificationEvent::PrChangesRequested => "PR CHANGES REQUIRED.png",
-            NotificationEvent::PrMerged => "PR MERGE COMPLETED.png",
-            NotificationEvent::PrNewComment => "PR NEW COMMENT APPEARED.png",
+    /// Registers the configured subtitle font, or the first available serif system font,
+    /// and returns the family to draw subtitles with.
+    fn install_subtitle_font(ctx: &egui::Context, config: &GuiConfig) -> FontFamily {
+        let path = config.font.clone().or_else(|| {
+            SERIF_FONT_CANDIDATES
+                .iter()
+                .find(|path| Path::new(path).is_file())
+                .map(|path| path.to_string())
+        });
+        let Some(path) = path else {
+            return FontFamily::Proportional;
+        };
+
+        let data = match fs::read(&path) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("Failed to read font {}: {}", path, e);
+                return FontFamily::Proportional;
+            }
+        };
+
+        let mut fonts = FontDefinitions::default();
+        fonts
+            .font_data
+            .insert(SUBTITLE_FONT_NAME.to_owned(), FontData::from_owned(data));
+        // Fall back to the default fonts for glyphs the subtitle font lacks, e.g. emoji.
+        let mut family = vec![SUBTITLE_FONT_NAME.to_owned()];
+        family.extend(fonts.families[&FontFamily::Proportional].iter().cloned());
+        fonts
+            .families
+            .insert(FontFamily::Name(SUBTITLE_FONT_NAME.into()), family);
+        ctx.set_fonts(fonts);
+
+        FontFamily::Name(SUBTITLE_FONT_NAME.into())
+    }
+
+    /// Drains newly received events into the queue.
+    fn receive(&mut self) {
+        while let Ok(event) = self.image_receiver.try_recv() {
+            println!("GUI: Received event to display: {:?}", event);
+            self.queue.push(event);
         }
     }
 
-    fn load_texture(&mut self, image_path: &str, ctx: &egui::Context) {
+    /// Advances the banner by `dt` seconds of wall-clock time.
+    ///
+    /// Returns `true` if a new notification started playing, whose texture the caller
+    /// still has to load.
+    ///
+    /// # Arguments
+    ///
+    /// * `dt` - The time since the previous frame, in seconds.
+    /// * `input` - The input seen since the previous frame.
+    fn tick(&mut self, dt: f64, input: FrameInput) -> bool {
+        if self.fatal_error.is_none()
+            && let Some(error) = self.status.fatal_error()
+        {
+            self.fail(error);
+        }
+
+        // A more important notification interrupts the current one; otherwise each
+        // notification plays its full cycle before the next one starts.
+        if matches!(self.state, AppState::FadingIn | AppState::Displaying)
+            && let Some(current) = &self.current
+            && self
+                .queue
+                .peek_priority()
+                .is_some_and(|priority| priority > current.event.priority)
+        {
+            self.preempt();
+        }
+
+        let mut started = false;
+        if self.state == AppState::Idle
+            && let Some(notification) = self.queue.pop()
+        {
+            self.start(notification);
+            started = true;
+        }
+
+        self.animation_time += dt;
+        match self.state {
+            AppState::Idle | AppState::Failed => {}
+            AppState::FadingIn => {
+                if self.animation_time >= FADE_SECS {
+                    self.state = AppState::Displaying;
+                    self.animation_time = 0.0;
+                }
+            }
+            AppState::Displaying => {
+                if self.should_dismiss(input) {
+                    self.state = AppState::FadingOut;
+                    self.animation_time = 0.0;
+                    self.current = None;
+                }
+            }
+            AppState::FadingOut => {
+                if self.animation_time >= FADE_SECS {
+                    self.state = AppState::Idle;
+                    self.animation_time = 0.0;
+                    self.texture = None;
+                    self.subtitle = None;
+                }
+            }
+        }
+        started
+    }
+
+    /// Starts playing a notification.
+    fn start(&mut self, notification: QueuedNotification) {
+        println!("GUI: Displaying: {}", notification.summary());
+        self.subtitle = self.config.gui.subtitles.then(|| notification.summary());
+        self.current = Some(notification);
+        self.state = AppState::FadingIn;
+        self.animation_time = 0.0;
+    }
+
+    /// Replaces whatever is on display with the error that stopped the event checker.
+    fn fail(&mut self, error: String) {
+        println!(
+            "GUI: Event checker stopped, closing in {}s",
+            FATAL_DISPLAY_SECS
+        );
+        self.fatal_error = Some(error);
+        self.current = None;
+        self.texture = None;
+        self.subtitle = None;
+        self.state = AppState::Failed;
+        self.animation_time = 0.0;
+    }
+
+    /// Returns whether the overlay is done and should close.
+    fn should_close(&self) -> bool {
+        self.status.is_shutting_down()
+            || (self.state == AppState::Failed && self.animation_time >= FATAL_DISPLAY_SECS)
+    }
+
+    /// Returns whether the banner on display should start fading out.
+    fn should_dismiss(&self, input: FrameInput) -> bool {
+        let Some(current) = &self.current else {
+            return true;
+        };
+        let kind = &current.event.kind;
+        let shown_long_enough =
+            self.animation_time >= self.config.display_time_for(kind).as_secs_f64();
+        match self.config.dismiss_for(kind) {
+            DismissPolicy::Duration => shown_long_enough,
+            DismissPolicy::Activity => shown_long_enough && input.activity,
+            DismissPolicy::Click => input.clicked,
+        }
+    }
+
+    /// Returns whether the overlay should receive mouse input instead of passing it through
+    /// to the windows beneath, which only the activity and click policies need.
+    fn captures_mouse(&self) -> bool {
+        let Some(current) = &self.current else {
+            return false;
+        };
+        let kind = &current.event.kind;
+        match self.config.dismiss_for(kind) {
+            DismissPolicy::Duration => false,
+            DismissPolicy::Activity => {
+                self.state == AppState::Displaying
+                    && self.animation_time >= self.config.display_time_for(kind).as_secs_f64()
+            }
+            DismissPolicy::Click => {
+                matches!(self.state, AppState::FadingIn | AppState::Displaying)
+            }
+        }
+    }
+
+    /// Returns the opacity of the banner, between 0 and 1.
+    fn opacity(&self) -> f64 {
+        match self.state {
+            AppState::Idle => 0.0,
+            AppState::FadingIn => (self.animation_time / FADE_SECS).min(1.0),
+            AppState::Displaying => 1.0,
+            AppState::FadingOut => (1.0 - self.animation_time / FADE_SECS).max(0.0),
+            AppState::Failed => (self.animation_time / FADE_SECS)
+                .min((FATAL_DISPLAY_SECS - self.animation_time) / FADE_SECS)
+                .clamp(0.0, 1.0),
+        }
+    }
+
+    /// Cuts the current notification short so a higher-priority one can play next.
+    ///
+    /// The banner fades out from its current opacity, and the interrupted notification is
+    /// re-queued or dropped according to the configured policy.
+    fn preempt(&mut self) {
+        let opacity = self.opacity();
+        self.state = AppState::FadingOut;
+        self.animation_time = (1.0 - opacity) * FADE_SECS;
+
+        let Some(current) = self.current.take() else {
+            return;
+        };
+        match self.config.queue.preempted {
+            PreemptPolicy::Requeue => {
+                println!("GUI: Preempted, re-queueing: {}", current.summary());
+                self.queue.requeue(current);
+            }
+            PreemptPolicy::Drop => {
+                println!("GUI: Preempted, dropping: {}", current.summary());
+            }
+        }
+    }
+
+    fn get_image_path_for_event(&self, event: &NotificationEvent) -> String {
+        self.config.image_for(&event.kind).to_string()
+    }
+
+    /// Reads an image either from the embedded assets or, failing that, from disk.
+    fn read_image(image_path: &str) -> Option<Cow<'static, [u8]>> {
         if let Some(asset) = Assets::get(image_path) {
-            if let Ok(decoded) = image::load_from_memory(&asset.data) {
+            return Some(asset.data);
+        }
+        fs::read(image_path).ok().map(Cow::Owned)
+    }
+
+    fn load_texture(&mut self, image_path: &str, ctx: &egui::Context) {
+        if let Some(data) = App::read_image(image_path) {
+            if let Ok(decoded) = image::load_from_memory(&data) {
                 let image = decoded.to_rgba8();
                 let (width, height) = image.dimensions();
                 let image_data = image.into_raw();
@@ -78,78 +418,101 @@ impl App {
                 self.texture =
                     Some(ctx.load_texture(image_path, color_image, TextureOptions::default()));
             } else {
-                eprintln!("Failed to decode embedded image: {}", image_path);
+                eprintln!("Failed to decode image: {}", image_path);
             }
         } else {
-            eprintln!("Failed to find embedded image: {}", image_path);
+            eprintln!("Failed to find image: {}", image_path);
         }
     }
 }
 
 impl eframe::App for App {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        if let Ok(event) = self.image_receiver.try_recv() {
-            println!("GUI: Received event to display: {:?}", event);
-            let image_path = App::get_image_path_for_event(&event);
-            self.load_texture(image_path, ctx);
-            self.state = AppState::FadingIn;
-            self.animation_time = 0.0;
-        }
+        self.receive();
 
-        self.animation_time += ctx.input(|i| i.unstable_dt) as f64;
-
-        let opacity = match self.state {
-            AppState::Idle => 0.0,
-            AppState::FadingIn => {
-                if self.animation_time >= 0.5 {
-                    self.state = AppState::Displaying;
-                    self.animation_time = 0.0;
-                    1.0
-                } else {
-                    self.animation_time / 0.5
-                }
-            }
-            AppState::Displaying => {
-                if self.animation_time < 2.0 {
-                    1.0
-                } else {
-                    if !ctx.input(|i| i.events.is_empty()) {
-                        self.state = AppState::FadingOut;
-                        self.animation_time = 0.0;
-                    }
-                    1.0
-                }
-            }
-            AppState::FadingOut => {
-                if self.animation_time >= 0.5 {
-                    self.state = AppState::Idle;
-                    self.texture = None;
-                    0.0
-                } else {
-                    1.0 - (self.animation_time / 0.5)
-                }
-            }
-        };
+        let dt = ctx.input(|i| i.unstable_dt) as f64;
+        let mut input = FrameInput::read(ctx, self.banner_rect);
+        input.activity |= self.keys.pressed();
+        if self.tick(dt, input)
+            && let Some(current) = &self.current
+        {
+            let image_path = self.get_image_path_for_event(&current.event);
+            self.load_texture(&image_path, ctx);
+        }
+        let opacity = self.opacity();
 
-        ctx.send_viewport_cmd(egui::ViewportCommand::MousePassthrough(opacity < 0.1));
+        if self.should_close() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+        ctx.send_viewport_cmd(egui::ViewportCommand::MousePassthrough(
+            !self.captures_mouse(),
+        ));
 
+        self.banner_rect = None;
         if let Some(texture) = &self.texture {
             let final_opacity = (opacity.clamp(0.0, 1.0) * 255.0) as u8;
             let screen_rect = ctx.screen_rect();
             let center = screen_rect.center();
             let image_pos = center - texture.size_vec2() / 2.0;
 
-            egui::Area::new("notification_area".into())
-                .fixed_pos(image_pos)
+            let banner =
+                egui::Area::new("notification_area".into())
+                    .fixed_pos(image_pos)
+                    .show(ctx, |ui| {
+                        ui.set_max_width(texture.size_vec2().x);
+                        ui.vertical_centered(|ui| {
+                            ui.add(egui::Image::new(texture).tint(
+                                Color32::from_rgba_unmultiplied(255, 255, 255, final_opacity),
+                            ));
+
+                            if let Some(subtitle) = &self.subtitle {
+                                let (r, g, b) = SUBTITLE_COLOR;
+                                let text = RichText::new(subtitle)
+                                    .font(self.subtitle_font.clone())
+                                    .color(Color32::from_rgba_unmultiplied(r, g, b, final_opacity));
+                                ui.add(egui::Label::new(text).wrap(true));
+                            }
+                        });
+                    });
+            self.banner_rect = Some(banner.response.rect);
+        }
+
+        if let Some(error) = &self.fatal_error {
+            let (r, g, b) = ERROR_COLOR;
+            let color = Color32::from_rgba_unmultiplied(r, g, b, (opacity * 255.0) as u8);
+            let mut heading_font = self.subtitle_font.clone();
+            heading_font.size *= 2.0;
+            let max_width = ctx.screen_rect().width() * 0.6;
+
+            egui::Area::new("error_area".into())
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .interactable(false)
+                .show(ctx, |ui| {
+                    ui.set_max_width(max_width);
+                    ui.vertical_centered(|ui| {
+                        ui.label(
+                            RichText::new("REPOSOULS HAS FALLEN")
+                                .font(heading_font)
+                                .color(color),
+                        );
+                        ui.add(
+                            egui::Label::new(
+                                RichText::new(error)
+                                    .font(self.subtitle_font.clone())
+                                    .color(color),
+                            )
+                            .wrap(true),
+                        );
+                    });
+                });
+        }
+
+        if self.status.is_offline() {
+            egui::Area::new("status_area".into())
+                .anchor(egui::Align2::RIGHT_TOP, [-16.0, 16.0])
+                .interactable(false)
                 .show(ctx, |ui| {
-                    ui.add(
-                        egui::Image::new(texture).tint(Color32::from_rgba_unmultiplied(
-                            255,
-                            255,
-                            255,
-                            final_opacity,
-                        )),
-                    );
+                    ui.label(RichText::new("⚠ offline").small().color(OFFLINE_COLOR));
                 });
         }
 
@@ -160,3 +523,250 @@ impl eframe::App for App {
         [0.0, 0.0, 0.0, 0.0]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::NotificationKind;
+    use std::sync::mpsc::{self, Sender};
+
+    /// The length of one frame at 60 frames per second.
+    const FRAME: f64 = 1.0 / 60.0;
+
+    /// Drives an `App` frame by frame in place of egui's clock.
+    struct FakeClock {
+        app: App,
+        sender: Sender<NotificationEvent>,
+    }
+
+    impl FakeClock {
+        fn new(config: Config) -> Self {
+            let (sender, receiver) = mpsc::channel();
+            FakeClock {
+                app: App::with_font(
+                    receiver,
+                    config,
+                    Arc::new(Status::new()),
+                    FontId::default(),
+                    KeyWatcher::disabled(),
+                ),
+                sender,
+            }
+        }
+
+        fn send(&self, kind: NotificationKind) {
+            let event = NotificationEvent {
+                priority: self.app.config.priority_for(&kind),
+                ..NotificationEvent::example(kind)
+            };
+            self.sender.send(event).unwrap();
+        }
+
+        /// Lets `secs` seconds pass without any input.
+        fn run(&mut self, secs: f64) {
+            for _ in 0..(secs / FRAME).round() as usize {
+                self.frame(FrameInput::default());
+            }
+        }
+
+        /// Plays a single frame with the given input.
+        fn frame(&mut self, input: FrameInput) {
+            self.app.receive();
+            self.app.tick(FRAME, input);
+        }
+
+        fn state(&self) -> &AppState {
+            &self.app.state
+        }
+    }
+
+    const ACTIVITY: FrameInput = FrameInput {
+        activity: true,
+        clicked: false,
+    };
+
+    const CLICK: FrameInput = FrameInput {
+        activity: true,
+        clicked: true,
+    };
+
+    fn config_with(dismiss: DismissPolicy) -> Config {
+        let mut config = Config::default();
+        config.gui.dismiss = dismiss;
+        config
+    }
+
+    #[test]
+    fn duration_policy_fades_out_after_display_time_without_input() {
+        let mut clock = FakeClock::new(config_with(DismissPolicy::Duration));
+        clock.send(NotificationKind::CiSuccess);
+
+        clock.run(0.6);
+        assert_eq!(clock.state(), &AppState::Displaying);
+        assert!(!clock.app.captures_mouse());
+
+        clock.frame(CLICK);
+        clock.run(2.8);
+        assert_eq!(clock.state(), &AppState::Displaying);
+
+        clock.run(0.3);
+        assert_eq!(clock.state(), &AppState::FadingOut);
+        clock.run(0.6);
+        assert_eq!(clock.state(), &AppState::Idle);
+        assert_eq!(clock.app.opacity(), 0.0);
+    }
+
+    #[test]
+    fn activity_policy_waits_for_input_after_display_time() {
+        let mut clock = FakeClock::new(config_with(DismissPolicy::Activity));
+        clock.send(NotificationKind::CiSuccess);
+
+        clock.run(1.0);
+        clock.frame(ACTIVITY);
+        assert_eq!(clock.state(), &AppState::Displaying);
+        assert!(!clock.app.captures_mouse());
+
+        clock.run(30.0);
+        assert_eq!(clock.state(), &AppState::Displaying);
+        assert!(clock.app.captures_mouse());
+
+        clock.frame(ACTIVITY);
+        assert_eq!(clock.state(), &AppState::FadingOut);
+        assert!(!clock.app.captures_mouse());
+    }
+
+    #[test]
+    fn only_clicks_on_the_banner_count() {
+        let banner = egui::Rect::from_min_size(egui::pos2(100.0, 100.0), egui::vec2(200.0, 50.0));
+        let click_at = |x, y| {
+            let pos = egui::pos2(x, y);
+            let raw = egui::RawInput {
+                events: vec![
+                    egui::Event::PointerMoved(pos),
+                    egui::Event::PointerButton {
+                        pos,
+                        button: egui::PointerButton::Primary,
+                        pressed: true,
+                        modifiers: egui::Modifiers::NONE,
+                    },
+                ],
+                ..Default::default()
+            };
+            let mut input = None;
+            let _ = egui::Context::default().run(raw, |ctx| {
+                input = Some(FrameInput::read(ctx, Some(banner)));
+            });
+            input.unwrap()
+        };
+
+        let inside = click_at(150.0, 120.0);
+        assert!(inside.clicked);
+        let outside = click_at(20.0, 20.0);
+        assert!(!outside.clicked);
+        assert!(outside.activity);
+
+        let mut clock = FakeClock::new(config_with(DismissPolicy::Click));
+        clock.send(NotificationKind::CiSuccess);
+        clock.run(1.0);
+        clock.frame(outside);
+        assert_eq!(clock.state(), &AppState::Displaying);
+        clock.frame(inside);
+        assert_eq!(clock.state(), &AppState::FadingOut);
+    }
+
+    #[test]
+    fn click_policy_ignores_other_activity() {
+        let mut clock = FakeClock::new(config_with(DismissPolicy::Click));
+        clock.send(NotificationKind::CiSuccess);
+
+        clock.frame(FrameInput::default());
+        assert!(clock.app.captures_mouse());
+
+        clock.run(30.0);
+        clock.frame(ACTIVITY);
+        assert_eq!(clock.state(), &AppState::Displaying);
+
+        clock.frame(CLICK);
+        assert_eq!(clock.state(), &AppState::FadingOut);
+    }
+
+    #[test]
+    fn click_policy_dismisses_before_display_time() {
+        let mut clock = FakeClock::new(config_with(DismissPolicy::Click));
+        clock.send(NotificationKind::CiSuccess);
+
+        clock.run(0.6);
+        clock.frame(CLICK);
+        assert_eq!(clock.state(), &AppState::FadingOut);
+    }
+
+    #[test]
+    fn policy_and_display_time_are_configurable_per_event() {
+        let mut config = config_with(DismissPolicy::Duration);
+        config.events.pr_new_comment.dismiss = Some(DismissPolicy::Click);
+        config.events.ci_success.display_secs = Some(1.0);
+        let mut clock = FakeClock::new(config);
+
+        clock.send(NotificationKind::PrNewComment);
+        clock.run(30.0);
+        assert_eq!(clock.state(), &AppState::Displaying);
+        clock.frame(CLICK);
+        clock.run(0.6);
+        assert_eq!(clock.state(), &AppState::Idle);
+
+        clock.send(NotificationKind::CiSuccess);
+        clock.run(1.6);
+        assert_eq!(clock.state(), &AppState::FadingOut);
+    }
+
+    #[test]
+    fn fatal_error_replaces_the_banner_and_closes_the_overlay() {
+        let mut clock = FakeClock::new(config_with(DismissPolicy::Click));
+        clock.send(NotificationKind::CiSuccess);
+        clock.run(1.0);
+
+        clock
+            .app
+            .status
+            .set_fatal_error("Could not get git info".to_string());
+        clock.frame(FrameInput::default());
+        assert_eq!(clock.state(), &AppState::Failed);
+        assert!(clock.app.current.is_none());
+        assert!(!clock.app.captures_mouse());
+
+        clock.send(NotificationKind::CiFailure);
+        clock.run(FATAL_DISPLAY_SECS - 1.0);
+        assert_eq!(clock.state(), &AppState::Failed);
+        assert!(!clock.app.should_close());
+
+        clock.run(1.0);
+        assert!(clock.app.should_close());
+    }
+
+    #[test]
+    fn shutdown_closes_the_overlay_at_once() {
+        let mut clock = FakeClock::new(config_with(DismissPolicy::Click));
+        clock.send(NotificationKind::CiSuccess);
+        clock.run(1.0);
+        assert!(!clock.app.should_close());
+
+        clock.app.status.request_shutdown();
+        assert!(clock.app.should_close());
+    }
+
+    #[test]
+    fn higher_priority_preempts_and_requeues_current_banner() {
+        let mut clock = FakeClock::new(config_with(DismissPolicy::Click));
+        clock.send(NotificationKind::PrNewComment);
+        clock.run(1.0);
+
+        clock.send(NotificationKind::CiFailure);
+        clock.frame(FrameInput::default());
+        assert_eq!(clock.state(), &AppState::FadingOut);
+        assert_eq!(clock.app.queue.len(), 2);
+
+        clock.run(0.6);
+        let current = clock.app.current.as_ref().unwrap();
+        assert_eq!(current.event.kind, NotificationKind::CiFailure);
+    }
+}
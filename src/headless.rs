@@ -0,0 +1,287 @@
+use crate::events::{NotificationEvent, NotificationKind};
+use crate::status::Status;
+use std::env;
+use std::io::{self, IsTerminal, Write};
+use std::sync::Arc;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+/// How often to check for a shutdown or a fatal error while no events arrive.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The terminal width assumed when `COLUMNS` is not set.
+const DEFAULT_COLUMNS: usize = 80;
+
+/// How far banners are indented from the left edge of the terminal.
+const INDENT: &str = "  ";
+
+/// The ANSI colour of the error shown when the event checker stops.
+const ERROR_COLOR: &str = "31";
+
+/// The letters of the banner font, five rows high. `#` marks a filled cell.
+const FONT: &[(char, [&str; 5])] = &[
+    ('A', [" ### ", "#   #", "#####", "#   #", "#   #"]),
+    ('B', ["#### ", "#   #", "#### ", "#   #", "#### "]),
+    ('C', [" ####", "#    ", "#    ", "#    ", " ####"]),
+    ('D', ["#### ", "#   #", "#   #", "#   #", "#### "]),
+    ('E', ["#####", "#    ", "#### ", "#    ", "#####"]),
+    ('F', ["#####", "#    ", "#### ", "#    ", "#    "]),
+    ('G', [" ####", "#    ", "#  ##", "#   #", " ####"]),
+    ('H', ["#   #", "#   #", "#####", "#   #", "#   #"]),
+    ('I', ["###", " # ", " # ", " # ", "###"]),
+    ('J', ["  ###", "   # ", "   # ", "#  # ", " ##  "]),
+    ('K', ["#   #", "#  # ", "###  ", "#  # ", "#   #"]),
+    ('L', ["#    ", "#    ", "#    ", "#    ", "#####"]),
+    ('M', ["#   #", "## ##", "# # #", "#   #", "#   #"]),
+    ('N', ["#   #", "##  #", "# # #", "#  ##", "#   #"]),
+    ('O', [" ### ", "#   #", "#   #", "#   #", " ### "]),
+    ('P', ["#### ", "#   #", "#### ", "#    ", "#    "]),
+    ('Q', [" ### ", "#   #", "# # #", "#  # ", " ## #"]),
+    ('R', ["#### ", "#   #", "#### ", "#  # ", "#   #"]),
+    ('S', [" ####", "#    ", " ### ", "    #", "#### "]),
+    ('T', ["#####", "  #  ", "  #  ", "  #  ", "  #  "]),
+    ('U', ["#   #", "#   #", "#   #", "#   #", " ### "]),
+    ('V', ["#   #", "#   #", "#   #", " # # ", "  #  "]),
+    ('W', ["#   #", "#   #", "# # #", "## ##", "#   #"]),
+    ('X', ["#   #", " # # ", "  #  ", " # # ", "#   #"]),
+    ('Y', ["#   #", " # # ", "  #  ", "  #  ", "  #  "]),
+    ('Z', ["#####", "   # ", "  #  ", " #   ", "#####"]),
+];
+
+/// The glyph drawn between words and for characters the font lacks.
+const BLANK: [&str; 5] = ["   "; 5];
+
+/// Prints events as terminal banners until a shutdown is requested or the event checker
+/// fails, whose error is then printed the same way.
+///
+/// # Arguments
+///
+/// * `image_receiver` - The channel the events arrive on.
+/// * `status` - The event checker's health, and whether a shutdown was requested.
+pub fn run_headless(image_receiver: Receiver<NotificationEvent>, status: Arc<Status>) {
+    let style = Style::detect();
+    loop {
+        match image_receiver.recv_timeout(POLL_INTERVAL) {
+            Ok(event) => {
+                let details = details_for(&event);
+                let kind = &event.kind;
+                print_banner(&style, headline_for(kind), color_for(kind), &details);
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            // The checker only stops on a shutdown or a fatal error, which it records just
+            // after letting go of the channel.
+            Err(RecvTimeoutError::Disconnected) => thread::sleep(POLL_INTERVAL),
+        }
+
+        if let Some(error) = status.fatal_error() {
+            print_banner(&style, "REPOSOULS HAS FALLEN", ERROR_COLOR, &[error]);
+            return;
+        }
+        if status.is_shutting_down() {
+            return;
+        }
+    }
+}
+
+/// Returns the headline of a banner, drawn in big letters.
+fn headline_for(kind: &NotificationKind) -> &'static str {
+    match kind {
+        NotificationKind::CiSuccess => "CI PIPELINE PASSED",
+        NotificationKind::CiFailure => "CI PIPELINE FAILED",
+        NotificationKind::PrApproved => "PULL REQUEST APPROVED",
+        NotificationKind::PrChangesRequested => "CHANGES REQUESTED",
+        NotificationKind::PrMerged => "PULL REQUEST MERGED",
+        NotificationKind::PrNewComment => "NEW COMMENT",
+    }
+}
+
+/// Returns the ANSI colour of a banner.
+fn color_for(kind: &NotificationKind) -> &'static str {
+    match kind {
+        NotificationKind::CiSuccess => "32",
+        NotificationKind::CiFailure => "31",
+        NotificationKind::PrApproved => "36",
+        NotificationKind::PrChangesRequested => "33",
+        NotificationKind::PrMerged => "35",
+        NotificationKind::PrNewComment => "34",
+    }
+}
+
+/// Returns the lines printed under the headline: the summary, the link and the target.
+fn details_for(event: &NotificationEvent) -> Vec<String> {
+    let payload = &event.payload;
+    let mut details = vec![event.summary()];
+    details.extend(payload.url.clone());
+    details.push(format!(
+        "{} · {}",
+        payload.target,
+        payload.timestamp.format("%H:%M:%S UTC")
+    ));
+    details
+}
+
+/// Whether output is styled with ANSI escape codes, and whether banners ring the bell.
+struct Style {
+    color: bool,
+    bell: bool,
+}
+
+impl Style {
+    /// Styles output only for a terminal, and without colours if `NO_COLOR` is set.
+    fn detect() -> Self {
+        let terminal = io::stdout().is_terminal();
+        Style {
+            color: terminal && env::var_os("NO_COLOR").is_none(),
+            bell: terminal,
+        }
+    }
+
+    /// Wraps `text` in the given SGR parameters, e.g. `1;31` for bold red.
+    fn paint(&self, text: &str, sgr: &str) -> String {
+        if self.color {
+            format!("\x1b[{}m{}\x1b[0m", sgr, text)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+fn print_banner(style: &Style, headline: &str, color: &str, details: &[String]) {
+    let columns = env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+        .unwrap_or(DEFAULT_COLUMNS);
+    let mut stdout = io::stdout().lock();
+    let _ = stdout.write_all(render_banner(style, headline, color, details, columns).as_bytes());
+    let _ = stdout.flush();
+}
+
+/// Renders a banner: the headline in big letters, a rule, then the details.
+///
+/// # Arguments
+///
+/// * `style` - How to style the banner.
+/// * `headline` - The text drawn in big letters.
+/// * `color` - The ANSI colour of the headline and rule.
+/// * `details` - The lines under the rule. The first is highlighted.
+/// * `columns` - The width of the terminal, which the headline is wrapped to.
+fn render_banner(
+    style: &Style,
+    headline: &str,
+    color: &str,
+    details: &[String],
+    columns: usize,
+) -> String {
+    let big = big_text(headline, columns.saturating_sub(INDENT.len()));
+    let rule_width = big
+        .iter()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let mut banner = String::new();
+    if style.bell {
+        banner.push('\x07');
+    }
+    banner.push('\n');
+    for line in &big {
+        if !line.is_empty() {
+            banner.push_str(INDENT);
+            banner.push_str(&style.paint(line, &format!("1;{}", color)));
+        }
+        banner.push('\n');
+    }
+    banner.push_str(&format!(
+        "{}{}\n",
+        INDENT,
+        style.paint(&"─".repeat(rule_width), color)
+    ));
+    for (i, detail) in details.iter().enumerate() {
+        let sgr = if i == 0 { "1" } else { "2" };
+        banner.push_str(&format!("{}{}\n", INDENT, style.paint(detail, sgr)));
+    }
+    banner.push('\n');
+    banner
+}
+
+/// Returns the glyph of a character, ignoring case.
+fn glyph(c: char) -> &'static [&'static str; 5] {
+    FONT.iter()
+        .find(|(letter, _)| *letter == c.to_ascii_uppercase())
+        .map(|(_, glyph)| glyph)
+        .unwrap_or(&BLANK)
+}
+
+/// Returns how many columns `text` takes in big letters.
+fn big_width(text: &str) -> usize {
+    let glyphs: usize = text.chars().map(|c| glyph(c)[0].len()).sum();
+    glyphs + text.chars().count().saturating_sub(1)
+}
+
+/// Draws `text` in big letters, wrapping between words to fit in `columns`. Wrapped lines
+/// are separated by an empty line.
+///
+/// A word too wide to fit on its own is drawn regardless, for the terminal to wrap.
+fn big_text(text: &str, columns: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        match lines.last_mut() {
+            Some(line) if big_width(&format!("{} {}", line, word)) <= columns => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_string()),
+        }
+    }
+
+    let mut rows = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            rows.push(String::new());
+        }
+        for row in 0..5 {
+            let cells: Vec<&str> = line.chars().map(|c| glyph(c)[row]).collect();
+            rows.push(cells.join(" ").replace('#', "█").trim_end().to_string());
+        }
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAIN: Style = Style {
+        color: false,
+        bell: false,
+    };
+
+    #[test]
+    fn headline_wraps_between_words() {
+        let rows = big_text("CI PIPELINE FAILED", 78);
+        assert_eq!(rows.len(), 11);
+        assert!(rows[5].is_empty());
+        assert!(rows.iter().all(|row| row.chars().count() <= 78));
+        assert_eq!(rows[6], "█████  ███  ███ █     █████ ████");
+
+        assert_eq!(big_text("CI PIPELINE FAILED", 200).len(), 5);
+    }
+
+    #[test]
+    fn plain_banners_have_no_escape_codes() {
+        let details = ["#7 Add retry logic — CI failed".to_string()];
+        let banner = render_banner(&PLAIN, "CI PIPELINE FAILED", "31", &details, 80);
+        assert!(!banner.contains('\x1b'));
+        assert!(!banner.contains('\x07'));
+        assert!(banner.contains("\n  #7 Add retry logic — CI failed\n"));
+
+        let ringing = Style {
+            color: true,
+            bell: true,
+        };
+        let banner = render_banner(&ringing, "CI PIPELINE FAILED", "31", &details, 80);
+        assert!(banner.starts_with('\x07'));
+        assert!(banner.contains("\x1b[1;31m"));
+    }
+}
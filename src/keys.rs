@@ -0,0 +1,194 @@
+//! Notices key presses anywhere on the desktop.
+//!
+//! The overlay never has the keyboard focus, so egui does not see key presses; they are
+//! read from the system instead. That works on X11, Windows and macOS. Elsewhere, e.g. on
+//! Wayland without XWayland, key presses are not noticed.
+
+/// Watches for key presses in any window.
+pub struct KeyWatcher(Option<platform::Watcher>);
+
+impl KeyWatcher {
+    /// Starts watching, or reports that key presses cannot be watched here.
+    pub fn new() -> Self {
+        let watcher = platform::Watcher::new();
+        if watcher.is_none() {
+            eprintln!("Key presses cannot be watched here; only the mouse dismisses banners");
+        }
+        KeyWatcher(watcher)
+    }
+
+    /// A watcher that never sees a key press.
+    #[cfg(test)]
+    pub fn disabled() -> Self {
+        KeyWatcher(None)
+    }
+
+    /// Returns whether a key was pressed since the previous call.
+    ///
+    /// On Windows, mouse input anywhere counts as well.
+    pub fn pressed(&mut self) -> bool {
+        self.0.as_mut().is_some_and(platform::Watcher::pressed)
+    }
+}
+
+/// Polls the X server for the keys held down, which it reports whichever window has the
+/// focus.
+#[cfg(any(
+    target_os = "linux",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+mod platform {
+    use std::ptr;
+    use x11_dl::xlib::{Display, Xlib};
+
+    pub struct Watcher {
+        xlib: Xlib,
+        display: *mut Display,
+        /// The keys held down at the previous poll, one bit per keycode.
+        keys: [u8; 32],
+    }
+
+    impl Watcher {
+        pub fn new() -> Option<Self> {
+            let xlib = Xlib::open().ok()?;
+            let display = unsafe { (xlib.XOpenDisplay)(ptr::null()) };
+            if display.is_null() {
+                return None;
+            }
+            let mut watcher = Watcher {
+                xlib,
+                display,
+                keys: [0; 32],
+            };
+            // Keys already held down are not new presses.
+            watcher.pressed();
+            Some(watcher)
+        }
+
+        pub fn pressed(&mut self) -> bool {
+            let mut keys = [0u8; 32];
+            unsafe { (self.xlib.XQueryKeymap)(self.display, keys.as_mut_ptr().cast()) };
+            let pressed = keys
+                .iter()
+                .zip(&self.keys)
+                .any(|(now, before)| now & !before != 0);
+            self.keys = keys;
+            pressed
+        }
+    }
+
+    impl Drop for Watcher {
+        fn drop(&mut self) {
+            unsafe { (self.xlib.XCloseDisplay)(self.display) };
+        }
+    }
+}
+
+/// Compares the time of the latest input, which Windows keeps for the whole session.
+#[cfg(windows)]
+mod platform {
+    use std::mem;
+
+    #[repr(C)]
+    struct LastInputInfo {
+        cb_size: u32,
+        dw_time: u32,
+    }
+
+    #[link(name = "user32")]
+    unsafe extern "system" {
+        fn GetLastInputInfo(plii: *mut LastInputInfo) -> i32;
+    }
+
+    fn last_input() -> Option<u32> {
+        let mut info = LastInputInfo {
+            cb_size: mem::size_of::<LastInputInfo>() as u32,
+            dw_time: 0,
+        };
+        (unsafe { GetLastInputInfo(&mut info) } != 0).then_some(info.dw_time)
+    }
+
+    pub struct Watcher {
+        /// The tick count of the latest input at the previous poll.
+        last_input: u32,
+    }
+
+    impl Watcher {
+        pub fn new() -> Option<Self> {
+            Some(Watcher {
+                last_input: last_input()?,
+            })
+        }
+
+        pub fn pressed(&mut self) -> bool {
+            let Some(last_input) = last_input() else {
+                return false;
+            };
+            let pressed = last_input != self.last_input;
+            self.last_input = last_input;
+            pressed
+        }
+    }
+}
+
+/// Asks the window server how long ago a key was last pressed in the session.
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::time::Instant;
+
+    /// `kCGEventSourceStateCombinedSessionState`.
+    const COMBINED_SESSION_STATE: i32 = 0;
+    /// `kCGEventKeyDown`.
+    const KEY_DOWN: u32 = 10;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    unsafe extern "C" {
+        fn CGEventSourceSecondsSinceLastEventType(state: i32, event_type: u32) -> f64;
+    }
+
+    pub struct Watcher {
+        polled: Instant,
+    }
+
+    impl Watcher {
+        pub fn new() -> Option<Self> {
+            Some(Watcher {
+                polled: Instant::now(),
+            })
+        }
+
+        pub fn pressed(&mut self) -> bool {
+            let since_key =
+                unsafe { CGEventSourceSecondsSinceLastEventType(COMBINED_SESSION_STATE, KEY_DOWN) };
+            let since_poll = self.polled.elapsed().as_secs_f64();
+            self.polled = Instant::now();
+            since_key < since_poll
+        }
+    }
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    windows,
+    target_os = "macos"
+)))]
+mod platform {
+    pub struct Watcher;
+
+    impl Watcher {
+        pub fn new() -> Option<Self> {
+            None
+        }
+
+        pub fn pressed(&mut self) -> bool {
+            false
+        }
+    }
+}
@@ -1,4 +1,37 @@
+//! Watches GitHub for CI results, reviews, comments and merges on the branches you work on
+//! and announces them.
+//!
+//! The library is split by cargo feature, all on by default:
+//!
+//! * `cli` - The command line (`cli`) and signal handling (`shutdown`) of the
+//!   `reposouls` binary.
+//! * `gui` - The on-screen overlay (`gui`), with eframe and the bundled images.
+//! * `headless` - Terminal banners (`headless`).
+//! * `keyring` - Reading tokens from the OS keyring in [`auth`].
+//!
+//! Without them, [`github::GitHubClient`] and [`events::run_event_checker`] are available
+//! on their own, for embedding in other tools.
+
+pub mod auth;
+#[cfg(feature = "cli")]
+pub mod cli;
+pub mod config;
 pub mod events;
 pub mod git;
 pub mod github;
+#[cfg(feature = "gui")]
 pub mod gui;
+#[cfg(feature = "headless")]
+pub mod headless;
+#[cfg(feature = "gui")]
+mod keys;
+pub mod queue;
+pub mod remote;
+#[cfg(feature = "cli")]
+pub mod shutdown;
+pub mod sink;
+pub mod state;
+pub mod status;
+pub mod target;
+#[cfg(test)]
+mod testing;
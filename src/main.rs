@@ -1,22 +1,185 @@
-use reposouls::events::{NotificationEvent, run_event_checker};
+use reposouls::auth::Credentials;
+use reposouls::cli::{Args, USAGE};
+use reposouls::config::Config;
+use reposouls::events::{NotificationEvent, resolve_host, run_event_checker};
+#[cfg(feature = "gui")]
 use reposouls::gui;
+#[cfg(feature = "headless")]
+use reposouls::headless;
+use reposouls::shutdown::{SHUTDOWN_TIMEOUT, handle_signals};
+use reposouls::sink::{self, EventSink, FanOut};
+use reposouls::status::Status;
+use std::env;
 use std::error::Error;
-use std::sync::mpsc;
+use std::process;
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver};
 use std::thread;
 use tokio::runtime::Runtime;
 
+/// Where events are shown.
+enum Frontend {
+    /// The on-screen overlay.
+    #[cfg(feature = "gui")]
+    Gui,
+    /// Banners in the terminal.
+    #[cfg(feature = "headless")]
+    Headless,
+}
+
+impl Frontend {
+    /// Picks the frontend, falling back to the terminal when built without the overlay.
+    ///
+    /// # Arguments
+    ///
+    /// * `headless` - Whether `--headless` was given.
+    fn choose(headless: bool) -> Result<Self, &'static str> {
+        #[cfg(feature = "headless")]
+        if headless || !cfg!(feature = "gui") {
+            return Ok(Frontend::Headless);
+        }
+        #[cfg(feature = "gui")]
+        if !headless {
+            return Ok(Frontend::Gui);
+        }
+        Err(if headless {
+            "reposouls was built without the `headless` feature, so --headless is unavailable."
+        } else {
+            "reposouls was built without the `gui` and `headless` features."
+        })
+    }
+
+    /// Shows events until the user closes the overlay, a shutdown is requested or the
+    /// event checker fails.
+    #[cfg_attr(not(feature = "gui"), allow(unused_variables))]
+    fn run(
+        self,
+        image_receiver: Receiver<NotificationEvent>,
+        config: Config,
+        status: Arc<Status>,
+    ) -> Result<(), String> {
+        match self {
+            #[cfg(feature = "gui")]
+            Frontend::Gui => gui::run_gui(image_receiver, config, status)
+                .map_err(|e| format!("GUI Error: {}", e)),
+            #[cfg(feature = "headless")]
+            Frontend::Headless => {
+                headless::run_headless(image_receiver, status);
+                Ok(())
+            }
+        }
+    }
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
+    let args = match Args::parse(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(2);
+        }
+    };
+    if args.help {
+        print!("{}", USAGE);
+        return Ok(());
+    }
+    let frontend = match Frontend::choose(args.headless) {
+        Ok(frontend) => frontend,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(2);
+        }
+    };
+
+    let mut config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Fatal: Could not load configuration. Error: {}", e);
+            process::exit(1);
+        }
+    };
+
+    if let Some(catch_up) = args.catch_up {
+        config.catch_up.enabled = catch_up;
+    }
+
+    let targets = if args.targets.is_empty() {
+        match config.targets() {
+            Ok(targets) => targets,
+            Err(e) => {
+                eprintln!("Fatal: Invalid watch-list. Error: {}", e);
+                process::exit(1);
+            }
+        }
+    } else {
+        args.targets
+    };
+
+    // Look every token up before opening the overlay, so a missing one is reported here.
+    // Targets that are not git repositories are reported by the event checker.
+    let credentials = Arc::new(Credentials::new());
+    for target in &targets {
+        if let Ok(host) = resolve_host(target, &config)
+            && let Err(e) = credentials.token_for(&host)
+        {
+            eprintln!("Fatal: {}", e);
+            process::exit(1);
+        }
+    }
+
     let (image_sender, image_receiver) = mpsc::channel::<NotificationEvent>();
+    let mut sinks: Vec<Box<dyn EventSink>> = vec![Box::new(image_sender)];
+    for sink_config in &config.sinks {
+        match sink::from_config(sink_config) {
+            Ok(sink) => sinks.push(sink),
+            Err(e) => {
+                eprintln!("Fatal: {}", e);
+                process::exit(1);
+            }
+        }
+    }
+    let sink: Arc<dyn EventSink> = Arc::new(FanOut::new(sinks));
+
+    let status = Arc::new(Status::new());
+    if let Err(e) = handle_signals(Arc::clone(&status)) {
+        eprintln!("Could not handle termination signals. Error: {}", e);
+    }
 
+    let checker_config = config.clone();
+    let checker_status = Arc::clone(&status);
+    // The checker reports a fatal error through `status`, so the frontend can show it
+    // before closing and the exit code can reflect it.
+    let (done_sender, done_receiver) = mpsc::channel::<()>();
     thread::spawn(move || {
-        let rt = Runtime::new().unwrap();
-        rt.block_on(async {
-            run_event_checker(image_sender).await;
-        });
+        let result = match Runtime::new() {
+            Ok(rt) => rt
+                .block_on(run_event_checker(
+                    sink,
+                    checker_config,
+                    targets,
+                    Arc::clone(&checker_status),
+                    credentials,
+                ))
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(format!("Could not start the async runtime. Error: {}", e)),
+        };
+        if let Err(e) = result {
+            eprintln!("Fatal: {}", e);
+            checker_status.set_fatal_error(e);
+        }
+        let _ = done_sender.send(());
     });
 
-    if let Err(e) = gui::run_gui(image_receiver) {
-        eprintln!("GUI Error: {}", e);
+    if let Err(e) = frontend.run(image_receiver, config, Arc::clone(&status)) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+
+    // Let the checker finish its poll and save its state, but not for too long.
+    status.request_shutdown();
+    let _ = done_receiver.recv_timeout(SHUTDOWN_TIMEOUT);
+    if status.fatal_error().is_some() {
+        process::exit(1);
     }
 
     Ok(())
@@ -0,0 +1,235 @@
+use crate::config::QueueConfig;
+use crate::events::NotificationEvent;
+use std::collections::VecDeque;
+
+/// A notification waiting to be shown, standing in for one or more coalesced events.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueuedNotification {
+    /// The most recent of the coalesced events, whose `count` includes the earlier ones.
+    pub event: NotificationEvent,
+}
+
+impl QueuedNotification {
+    /// Describes the notification in one line, e.g. `3 new comments on #142 Add retry logic`.
+    pub fn summary(&self) -> String {
+        self.event.summary()
+    }
+
+    /// Returns whether `event` can be folded into this notification.
+    fn can_coalesce(&self, event: &NotificationEvent) -> bool {
+        self.event.kind == event.kind
+            && self.event.payload.target == event.payload.target
+            && self.event.payload.pr_number == event.payload.pr_number
+    }
+}
+
+/// A queue of notifications waiting for the banner to become free.
+///
+/// Notifications come out highest priority first and in arrival order within a priority.
+#[derive(Debug)]
+pub struct NotificationQueue {
+    pending: VecDeque<QueuedNotification>,
+    config: QueueConfig,
+}
+
+impl NotificationQueue {
+    /// Creates an empty queue.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The coalescing and length settings.
+    pub fn new(config: QueueConfig) -> Self {
+        Self {
+            pending: VecDeque::new(),
+            config,
+        }
+    }
+
+    /// Adds an event to the back of the queue.
+    ///
+    /// If coalescing is enabled and an event of the same kind for the same target and pull
+    /// request is already waiting, the two are merged in place instead. When the queue is
+    /// full, the oldest notification of the lowest priority is dropped, which may be the
+    /// new one.
+    pub fn push(&mut self, event: NotificationEvent) {
+        if self.config.coalesce
+            && let Some(queued) = self
+                .pending
+                .iter_mut()
+                .find(|queued| queued.can_coalesce(&event))
+        {
+            let count = queued.event.count + event.count;
+            queued.event = NotificationEvent { count, ..event };
+            return;
+        }
+
+        self.pending.push_back(QueuedNotification { event });
+        self.trim();
+    }
+
+    /// Puts an interrupted notification back, ahead of the others of its priority.
+    ///
+    /// When the queue is full, the oldest notification of the lowest priority is dropped,
+    /// which may be this one.
+    pub fn requeue(&mut self, notification: QueuedNotification) {
+        self.pending.push_front(notification);
+        self.trim();
+    }
+
+    /// Drops the oldest notifications of the lowest priority until the queue is no longer
+    /// than `max_len`.
+    fn trim(&mut self) {
+        while self.pending.len() > self.config.max_len.max(1) {
+            let Some(lowest) = self
+                .pending
+                .iter()
+                .map(|queued| queued.event.priority)
+                .min()
+            else {
+                return;
+            };
+            let index = self
+                .pending
+                .iter()
+                .position(|queued| queued.event.priority == lowest);
+            if let Some(dropped) = index.and_then(|index| self.pending.remove(index)) {
+                eprintln!("Notification queue full, dropping: {}", dropped.summary());
+            }
+        }
+    }
+
+    /// Removes and returns the earliest notification of the highest priority.
+    pub fn pop(&mut self) -> Option<QueuedNotification> {
+        let priority = self.peek_priority()?;
+        let index = self
+            .pending
+            .iter()
+            .position(|queued| queued.event.priority == priority)?;
+        self.pending.remove(index)
+    }
+
+    /// Returns the highest priority among the waiting notifications.
+    pub fn peek_priority(&self) -> Option<u8> {
+        self.pending
+            .iter()
+            .map(|queued| queued.event.priority)
+            .max()
+    }
+
+    /// Returns the number of notifications waiting.
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Returns whether no notifications are waiting.
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PreemptPolicy;
+    use crate::events::NotificationKind;
+
+    fn queue(coalesce: bool, max_len: usize) -> NotificationQueue {
+        NotificationQueue::new(QueueConfig {
+            coalesce,
+            max_len,
+            preempted: PreemptPolicy::Requeue,
+        })
+    }
+
+    fn event(kind: NotificationKind, target: &str, pr_number: u64) -> NotificationEvent {
+        let mut event = NotificationEvent::example(kind);
+        event.payload.target = target.to_string();
+        event.payload.pr_number = Some(pr_number);
+        event
+    }
+
+    #[test]
+    fn events_are_coalesced_by_kind_target_and_pull_request() {
+        let mut queue = queue(true, 10);
+        for _ in 0..3 {
+            queue.push(event(NotificationKind::PrNewComment, ".", 142));
+        }
+        queue.push(event(NotificationKind::PrNewComment, ".", 7));
+        queue.push(event(NotificationKind::PrNewComment, "../other", 142));
+        queue.push(event(NotificationKind::PrApproved, ".", 142));
+        assert_eq!(queue.len(), 4);
+
+        assert_eq!(queue.pop().unwrap().event.count, 1); // The approval comes first.
+        let comments = queue.pop().unwrap();
+        assert_eq!(comments.event.count, 3);
+        assert_eq!(comments.summary(), "3 new comments on #142 Add retry logic");
+        assert_eq!(queue.pop().unwrap().event.payload.pr_number, Some(7));
+        assert_eq!(queue.pop().unwrap().event.payload.target, "../other");
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn events_are_kept_apart_without_coalescing() {
+        let mut queue = queue(false, 10);
+        for _ in 0..3 {
+            queue.push(event(NotificationKind::PrNewComment, ".", 142));
+        }
+        assert_eq!(queue.len(), 3);
+        while let Some(notification) = queue.pop() {
+            assert_eq!(notification.event.count, 1);
+        }
+    }
+
+    #[test]
+    fn lowest_priority_notification_is_dropped_when_full() {
+        let mut queue = queue(false, 2);
+        queue.push(event(NotificationKind::CiFailure, ".", 1));
+        queue.push(event(NotificationKind::PrNewComment, ".", 1));
+        queue.push(event(NotificationKind::PrNewComment, ".", 2));
+        queue.push(event(NotificationKind::PrNewComment, ".", 3));
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.pop().unwrap().event.kind, NotificationKind::CiFailure);
+        assert_eq!(queue.pop().unwrap().event.payload.pr_number, Some(3));
+
+        // A new notification of the lowest priority is the one dropped.
+        queue.push(event(NotificationKind::CiFailure, ".", 1));
+        queue.push(event(NotificationKind::PrMerged, ".", 1));
+        queue.push(event(NotificationKind::PrNewComment, ".", 4));
+        let kinds: Vec<_> = std::iter::from_fn(|| queue.pop())
+            .map(|notification| notification.event.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            [NotificationKind::CiFailure, NotificationKind::PrMerged]
+        );
+    }
+
+    #[test]
+    fn requeued_notifications_respect_the_cap() {
+        let mut queue = queue(false, 2);
+        queue.push(event(NotificationKind::CiFailure, ".", 1));
+        queue.push(event(NotificationKind::CiFailure, ".", 2));
+        let interrupted = QueuedNotification {
+            event: event(NotificationKind::PrApproved, ".", 3),
+        };
+        queue.requeue(interrupted);
+        assert_eq!(queue.len(), 2);
+        assert!(
+            std::iter::from_fn(|| queue.pop())
+                .all(|notification| notification.event.kind == NotificationKind::CiFailure)
+        );
+    }
+
+    #[test]
+    fn oldest_notification_is_dropped_when_full() {
+        let mut queue = queue(false, 2);
+        for pr_number in 1..=3 {
+            queue.push(event(NotificationKind::PrMerged, ".", pr_number));
+        }
+        assert_eq!(queue.len(), 2);
+        let remaining: Vec<_> = std::iter::from_fn(|| queue.pop())
+            .map(|notification| notification.event.payload.pr_number)
+            .collect();
+        assert_eq!(remaining, [Some(2), Some(3)]);
+    }
+}
@@ -0,0 +1,40 @@
+use crate::status::Status;
+use std::process;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+/// How long a graceful shutdown may take before the process exits regardless.
+pub const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Shuts reposouls down gracefully on SIGINT or SIGTERM (Ctrl+C or closing the console on
+/// Windows).
+///
+/// The first signal asks the event checker to stop polling and save its state, and the GUI
+/// to close. If that takes longer than [`SHUTDOWN_TIMEOUT`], or a second signal arrives,
+/// the process exits at once.
+///
+/// # Arguments
+///
+/// * `status` - Where the shutdown is requested.
+pub fn handle_signals(status: Arc<Status>) -> Result<(), ctrlc::Error> {
+    let signalled = AtomicBool::new(false);
+    ctrlc::set_handler(move || {
+        if signalled.swap(true, Ordering::SeqCst) {
+            eprintln!("Signalled again, exiting now.");
+            process::exit(1);
+        }
+
+        println!("Shutting down...");
+        status.request_shutdown();
+        thread::spawn(|| {
+            thread::sleep(SHUTDOWN_TIMEOUT);
+            eprintln!(
+                "Did not shut down within {}s, exiting now.",
+                SHUTDOWN_TIMEOUT.as_secs()
+            );
+            process::exit(1);
+        });
+    })
+}
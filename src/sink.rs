@@ -0,0 +1,303 @@
+use crate::config::SinkConfig;
+use crate::events::NotificationEvent;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::{Mutex, mpsc};
+use std::thread;
+use thiserror::Error;
+
+/// Why an event could not be delivered.
+#[derive(Debug, Error)]
+pub enum SinkError {
+    /// The sink no longer takes events, e.g. because the GUI has gone away.
+    #[error("The event sink is closed")]
+    Closed,
+    /// This event could not be delivered, but later ones may be.
+    #[error("{0}")]
+    Failed(String),
+}
+
+/// A destination for notification events.
+///
+/// Sinks are called from the event checker's tasks, so they should return quickly.
+pub trait EventSink: Send + Sync {
+    /// Delivers an event.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The event to deliver.
+    fn deliver(&self, event: &NotificationEvent) -> Result<(), SinkError>;
+}
+
+/// Sends events to the GUI, or whatever else receives from the channel.
+impl EventSink for mpsc::Sender<NotificationEvent> {
+    fn deliver(&self, event: &NotificationEvent) -> Result<(), SinkError> {
+        self.send(event.clone()).map_err(|_| SinkError::Closed)
+    }
+}
+
+/// Delivers every event to several sinks.
+///
+/// A sink failing to deliver an event is reported and does not keep the event from the
+/// others. The fan-out closes once all of its sinks have.
+pub struct FanOut {
+    sinks: Vec<Box<dyn EventSink>>,
+}
+
+impl FanOut {
+    /// Creates a fan-out to the given sinks.
+    ///
+    /// # Arguments
+    ///
+    /// * `sinks` - The sinks, in the order events are delivered to them.
+    pub fn new(sinks: Vec<Box<dyn EventSink>>) -> Self {
+        Self { sinks }
+    }
+}
+
+impl EventSink for FanOut {
+    fn deliver(&self, event: &NotificationEvent) -> Result<(), SinkError> {
+        let mut closed = 0;
+        for sink in &self.sinks {
+            match sink.deliver(event) {
+                Ok(()) => {}
+                Err(SinkError::Closed) => closed += 1,
+                Err(e) => eprintln!("Failed to deliver event: {}", e),
+            }
+        }
+        if closed == self.sinks.len() {
+            Err(SinkError::Closed)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Prints a line per event to standard output.
+pub struct StdoutSink;
+
+impl EventSink for StdoutSink {
+    fn deliver(&self, event: &NotificationEvent) -> Result<(), SinkError> {
+        let payload = &event.payload;
+        let mut line = format!(
+            "{} [{}] {}",
+            payload.timestamp.format("%Y-%m-%d %H:%M:%S UTC"),
+            payload.target,
+            event.summary()
+        );
+        if let Some(url) = &payload.url {
+            line.push_str(&format!(" <{}>", url));
+        }
+        let mut stdout = io::stdout().lock();
+        writeln!(stdout, "{}", line)
+            .map_err(|e| SinkError::Failed(format!("Failed to write to stdout: {}", e)))
+    }
+}
+
+/// Appends each event to a file as a line of JSON.
+pub struct JsonlSink {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl JsonlSink {
+    /// Opens the file for appending, creating it and its directory if needed.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file to append to.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, String> {
+        let path = path.into();
+        if let Some(dir) = path.parent()
+            && !dir.as_os_str().is_empty()
+        {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("Failed to open event log {}: {}", path.display(), e))?;
+        Ok(Self {
+            path,
+            file: Mutex::new(file),
+        })
+    }
+}
+
+impl EventSink for JsonlSink {
+    fn deliver(&self, event: &NotificationEvent) -> Result<(), SinkError> {
+        let mut line = serde_json::to_string(event)
+            .map_err(|e| SinkError::Failed(format!("Failed to encode event as JSON: {}", e)))?;
+        line.push('\n');
+        // One write per line, so concurrent targets never interleave within a line.
+        self.file
+            .lock()
+            .unwrap()
+            .write_all(line.as_bytes())
+            .map_err(|e| {
+                SinkError::Failed(format!("Failed to write to {}: {}", self.path.display(), e))
+            })
+    }
+}
+
+/// Runs a shell command per event.
+///
+/// The command gets the event as JSON on its standard input and its main fields in the
+/// `REPOSOULS_EVENT`, `REPOSOULS_SUMMARY`, `REPOSOULS_TARGET`, `REPOSOULS_REPO` and, if
+/// there is one, `REPOSOULS_URL` environment variables. It runs in the background, so a
+/// slow command does not hold up polling.
+pub struct CommandSink {
+    command: String,
+}
+
+impl CommandSink {
+    /// Creates a sink running `command` with the system shell.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command, run with `sh -c` (`cmd /C` on Windows).
+    pub fn new(command: String) -> Self {
+        Self { command }
+    }
+}
+
+impl EventSink for CommandSink {
+    fn deliver(&self, event: &NotificationEvent) -> Result<(), SinkError> {
+        let json = serde_json::to_string(event)
+            .map_err(|e| SinkError::Failed(format!("Failed to encode event as JSON: {}", e)))?;
+        let (shell, flag) = if cfg!(windows) {
+            ("cmd", "/C")
+        } else {
+            ("sh", "-c")
+        };
+        let payload = &event.payload;
+        let mut command = Command::new(shell);
+        command
+            .args([flag, &self.command])
+            .env("REPOSOULS_EVENT", event.kind.key())
+            .env("REPOSOULS_SUMMARY", event.summary())
+            .env("REPOSOULS_TARGET", &payload.target)
+            .env("REPOSOULS_REPO", &payload.repo)
+            .stdin(Stdio::piped());
+        if let Some(url) = &payload.url {
+            command.env("REPOSOULS_URL", url);
+        }
+        let mut child = command
+            .spawn()
+            .map_err(|e| SinkError::Failed(format!("Failed to run `{}`: {}", self.command, e)))?;
+
+        let description = self.command.clone();
+        thread::spawn(move || {
+            // The command may not read its input, which is fine.
+            if let Some(mut stdin) = child.stdin.take() {
+                let _ = stdin.write_all(json.as_bytes());
+            }
+            match child.wait() {
+                Ok(status) if !status.success() => {
+                    eprintln!("Command `{}` failed: {}", description, status);
+                }
+                Ok(_) => {}
+                Err(e) => eprintln!("Failed to wait for `{}`: {}", description, e),
+            }
+        });
+        Ok(())
+    }
+}
+
+/// Creates the sink described in the configuration.
+///
+/// # Arguments
+///
+/// * `config` - The sink's settings.
+pub fn from_config(config: &SinkConfig) -> Result<Box<dyn EventSink>, String> {
+    Ok(match config {
+        SinkConfig::Stdout => Box::new(StdoutSink),
+        SinkConfig::Jsonl { path } => Box::new(JsonlSink::open(path)?),
+        SinkConfig::Command { command } => Box::new(CommandSink::new(command.clone())),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::NotificationKind;
+    use crate::testing::TempDir;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn fan_out_delivers_to_every_sink_until_all_close() {
+        let (gui, gui_events) = mpsc::channel();
+        let (other, other_events) = mpsc::channel();
+        let fan_out = FanOut::new(vec![Box::new(gui), Box::new(other)]);
+
+        fan_out
+            .deliver(&NotificationEvent::example(NotificationKind::CiFailure))
+            .unwrap();
+        assert_eq!(gui_events.try_iter().count(), 1);
+        assert_eq!(other_events.try_iter().count(), 1);
+
+        drop(gui_events);
+        fan_out
+            .deliver(&NotificationEvent::example(NotificationKind::CiSuccess))
+            .unwrap();
+        assert_eq!(other_events.try_iter().count(), 1);
+
+        drop(other_events);
+        assert!(matches!(
+            fan_out.deliver(&NotificationEvent::example(NotificationKind::CiSuccess)),
+            Err(SinkError::Closed)
+        ));
+    }
+
+    #[test]
+    fn jsonl_sink_appends_a_line_per_event() {
+        let dir = TempDir::new("sink-jsonl");
+        let path = dir.path().join("events.jsonl");
+        let sink = JsonlSink::open(&path).unwrap();
+        sink.deliver(&NotificationEvent::example(NotificationKind::CiFailure))
+            .unwrap();
+        sink.deliver(&NotificationEvent::example(NotificationKind::PrMerged))
+            .unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        let kinds: Vec<String> = contents
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .map(|value| value["kind"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(kinds, ["ci_failure", "pr_merged"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn command_sink_passes_the_event_to_the_command() {
+        let dir = TempDir::new("sink-command");
+        let path = dir.path().join("command.out");
+        let sink = CommandSink::new(format!(
+            "{{ echo \"$REPOSOULS_EVENT\"; cat; }} > {}.tmp && mv {}.tmp {}",
+            path.display(),
+            path.display(),
+            path.display()
+        ));
+        sink.deliver(&NotificationEvent::example(NotificationKind::CiFailure))
+            .unwrap();
+
+        let started = Instant::now();
+        while !path.exists() {
+            assert!(
+                started.elapsed() < Duration::from_secs(5),
+                "command did not run"
+            );
+            thread::sleep(Duration::from_millis(20));
+        }
+        let output = fs::read_to_string(&path).unwrap();
+        let (kind, json) = output.split_once('\n').unwrap();
+        assert_eq!(kind, "ci_failure");
+        let value: serde_json::Value = serde_json::from_str(json).unwrap();
+        assert_eq!(value["payload"]["pr_number"], 7);
+    }
+}
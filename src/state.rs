@@ -0,0 +1,166 @@
+use crate::git::GitInfo;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The name of the directory under `dirs::data_dir()` holding the saved state.
+const STATE_DIR_NAME: &str = "reposouls";
+
+/// What the event checker knew about a branch when it last checked it.
+///
+/// Saved after every poll so that events which happen while reposouls is not running can
+/// be caught up on at the next start.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SavedState {
+    /// When the branch was last checked. Events after this have not been seen yet.
+    pub last_checked: DateTime<Utc>,
+    /// The workflow runs already reported.
+    #[serde(default)]
+    pub seen_workflow_runs: HashSet<i64>,
+    /// The pull request comments already reported.
+    #[serde(default)]
+    pub seen_comments: HashSet<i64>,
+    /// The pull request reviews already reported.
+    #[serde(default)]
+    pub seen_reviews: HashSet<i64>,
+    /// The number of the branch's pull request, once it has been merged. A pull request
+    /// opened from the branch later is watched again.
+    #[serde(default)]
+    pub merged_pr: Option<u64>,
+    /// Workflow runs created before this were not listed any more. It stays before runs
+    /// that were still in progress, so their results are not lost. `last_checked` if unset.
+    #[serde(default)]
+    pub runs_since: Option<DateTime<Utc>>,
+    /// Comments not updated since this were not listed any more. `last_checked` if unset.
+    #[serde(default)]
+    pub comments_since: Option<DateTime<Utc>>,
+}
+
+impl SavedState {
+    /// Reads the state saved at `path`. Returns `None` if there is none.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The state file, as returned by [`state_path`].
+    pub fn load(path: &Path) -> Result<Option<Self>, String> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(format!(
+                    "Failed to read state file {}: {}",
+                    path.display(),
+                    e
+                ));
+            }
+        };
+        serde_json::from_str(&contents)
+            .map(Some)
+            .map_err(|e| format!("Invalid state file {}: {}", path.display(), e))
+    }
+
+    /// Writes the state to `path`, creating its directory if needed.
+    ///
+    /// The file is replaced atomically, so a crash mid-write leaves the previous state.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The state file, as returned by [`state_path`].
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let contents = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        let temp_path = path.with_extension("json.tmp");
+        fs::write(&temp_path, contents)
+            .and_then(|_| fs::rename(&temp_path, path))
+            .map_err(|e| format!("Failed to write state file {}: {}", path.display(), e))
+    }
+}
+
+/// Returns the file the state of the watched branch is saved in, or `None` for a
+/// detached HEAD, whose state is not kept across restarts.
+///
+/// The file lives at `<data dir>/reposouls/state/<host>/<owner>/<repo>/<branch>.json`,
+/// where owner and repo are those of the repository the branch is pushed to.
+///
+/// # Arguments
+///
+/// * `git_info` - The branch being watched.
+pub fn state_path(git_info: &GitInfo) -> Option<PathBuf> {
+    let branch = git_info.branch.as_deref()?;
+    let data_dir = dirs::data_dir()?;
+    Some(
+        data_dir
+            .join(STATE_DIR_NAME)
+            .join("state")
+            .join(escape(&git_info.host))
+            .join(escape(&git_info.head.owner))
+            .join(escape(&git_info.head.repo))
+            .join(format!("{}.json", escape(branch))),
+    )
+}
+
+/// Makes a name safe to use as a single path component, e.g. a branch like `feature/x`.
+fn escape(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for byte in name.bytes() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' => escaped.push(byte as char),
+            b'.' if !escaped.is_empty() => escaped.push('.'),
+            _ => escaped.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn saved_state_round_trips() {
+        let dir = TempDir::new("state");
+        let path = dir.path().join("github.com/octo/hello/main.json");
+        assert_eq!(SavedState::load(&path), Ok(None));
+
+        let state = SavedState {
+            last_checked: "2024-05-01T10:00:00Z".parse().unwrap(),
+            seen_workflow_runs: HashSet::from([1, 2]),
+            seen_comments: HashSet::from([3]),
+            seen_reviews: HashSet::new(),
+            merged_pr: Some(42),
+            runs_since: Some("2024-05-01T09:30:00Z".parse().unwrap()),
+            comments_since: Some("2024-05-01T09:50:00Z".parse().unwrap()),
+        };
+        state.save(&path).unwrap();
+        assert_eq!(SavedState::load(&path), Ok(Some(state)));
+        assert!(!path.with_extension("json.tmp").exists());
+
+        fs::write(&path, "{").unwrap();
+        let error = SavedState::load(&path).unwrap_err();
+        assert!(
+            error.starts_with(&format!("Invalid state file {}", path.display())),
+            "unexpected error: {}",
+            error
+        );
+    }
+
+    #[test]
+    fn names_are_escaped_into_single_path_components() {
+        assert_eq!(escape("main"), "main");
+        assert_eq!(escape("feature/x"), "feature%2Fx");
+        assert_eq!(escape("release-1.2_rc"), "release-1.2_rc");
+        assert_eq!(escape(".."), "%2E.");
+        assert_eq!(escape("."), "%2E");
+        assert_eq!(escape(".hidden"), "%2Ehidden");
+        assert_eq!(escape("a\\b:c"), "a%5Cb%3Ac");
+        assert_eq!(escape("héllo"), "h%C3%A9llo");
+        // Escaping is reversible, so distinct branches never share a file.
+        assert_ne!(escape("feature/x"), escape("feature%2Fx"));
+    }
+}
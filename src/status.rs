@@ -0,0 +1,82 @@
+use std::collections::HashSet;
+use std::sync::Mutex;
+use tokio::sync::watch;
+
+/// The health of the event checker, shared with the GUI so it can show it, and whether
+/// reposouls is shutting down.
+#[derive(Debug, Default)]
+pub struct Status {
+    /// The targets whose last poll could not reach GitHub.
+    offline_targets: Mutex<HashSet<String>>,
+    /// The error that stopped the event checker, if any.
+    fatal_error: Mutex<Option<String>>,
+    /// Whether a shutdown was requested, e.g. by a signal.
+    shutdown: watch::Sender<bool>,
+}
+
+impl Status {
+    /// Creates a status with every target online.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records whether a target could reach GitHub on its last poll.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The target's label.
+    /// * `offline` - Whether the poll failed because GitHub was unreachable.
+    pub fn set_offline(&self, target: &str, offline: bool) {
+        let mut offline_targets = self.offline_targets.lock().unwrap();
+        if offline {
+            offline_targets.insert(target.to_string());
+        } else {
+            offline_targets.remove(target);
+        }
+    }
+
+    /// Returns whether the given target could not reach GitHub on its last poll.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The target's label.
+    pub fn is_target_offline(&self, target: &str) -> bool {
+        self.offline_targets.lock().unwrap().contains(target)
+    }
+
+    /// Returns whether any target currently cannot reach GitHub.
+    pub fn is_offline(&self) -> bool {
+        !self.offline_targets.lock().unwrap().is_empty()
+    }
+
+    /// Records that the event checker stopped because of an error.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The error, as shown to the user.
+    pub fn set_fatal_error(&self, message: String) {
+        *self.fatal_error.lock().unwrap() = Some(message);
+    }
+
+    /// Returns the error that stopped the event checker, if it failed.
+    pub fn fatal_error(&self) -> Option<String> {
+        self.fatal_error.lock().unwrap().clone()
+    }
+
+    /// Asks the event checker and the GUI to stop.
+    pub fn request_shutdown(&self) {
+        self.shutdown.send_replace(true);
+    }
+
+    /// Returns whether a shutdown was requested.
+    pub fn is_shutting_down(&self) -> bool {
+        *self.shutdown.borrow()
+    }
+
+    /// Waits until a shutdown is requested, returning at once if it already was.
+    pub async fn shutdown_requested(&self) {
+        let mut receiver = self.shutdown.subscribe();
+        // The sender lives as long as `self`, so waiting cannot fail.
+        let _ = receiver.wait_for(|requested| *requested).await;
+    }
+}
@@ -0,0 +1,348 @@
+use crate::git::RepoSlug;
+use crate::github::GITHUB_HOST;
+use serde::Deserialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Something to monitor: a local checkout, or a branch of a repository on GitHub.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    /// A local git repository or worktree.
+    Local {
+        /// The directory of the checkout, or any directory inside it.
+        path: PathBuf,
+        /// The branch to watch. When `None`, the checked-out branch is followed.
+        branch: Option<String>,
+    },
+    /// A branch of a repository that is not checked out locally.
+    Remote {
+        /// The host serving the repository, e.g. `github.com`.
+        host: String,
+        /// The repository.
+        repo: RepoSlug,
+        /// The branch to watch.
+        branch: String,
+    },
+}
+
+/// A watch-list entry as written in the configuration file.
+///
+/// Exactly one of `path` and `repo` must be set.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct TargetConfig {
+    /// The directory of a local checkout.
+    pub path: Option<PathBuf>,
+    /// A repository on GitHub, in `owner/repo` form.
+    pub repo: Option<String>,
+    /// The host serving `repo`. Defaults to `github.com`. Only read from the global
+    /// configuration file, as the host's token is sent there.
+    pub host: Option<String>,
+    /// The branch to watch. Required for `repo`; for `path` defaults to the checked-out branch.
+    pub branch: Option<String>,
+}
+
+impl Target {
+    /// The current directory, following its checked-out branch.
+    pub fn current_dir() -> Self {
+        Target::Local {
+            path: PathBuf::from("."),
+            branch: None,
+        }
+    }
+
+    /// Parses a target given on the command line.
+    ///
+    /// Accepted forms are `PATH[@BRANCH]` for an existing directory, and
+    /// `[HOST/]OWNER/REPO@BRANCH` for a repository on GitHub.
+    ///
+    /// # Arguments
+    ///
+    /// * `spec` - The command-line argument.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        // A directory whose name contains `@` is still just a directory.
+        if Path::new(spec).is_dir() {
+            return Ok(Target::Local {
+                path: PathBuf::from(spec),
+                branch: None,
+            });
+        }
+
+        let (location, branch) = match spec.rsplit_once('@') {
+            Some((location, branch)) if !branch.is_empty() => (location, Some(branch.to_string())),
+            _ => (spec, None),
+        };
+
+        if location.is_empty() {
+            return Err(format!(
+                "Invalid target '{}': missing path or repository",
+                spec
+            ));
+        }
+
+        if Path::new(location).is_dir() {
+            return Ok(Target::Local {
+                path: PathBuf::from(location),
+                branch,
+            });
+        }
+
+        let segments: Vec<&str> = location.split('/').collect();
+        let (host, owner, repo) = match segments.as_slice() {
+            [owner, repo] => (GITHUB_HOST, *owner, *repo),
+            [host, owner, repo] => (*host, *owner, *repo),
+            _ => {
+                return Err(format!(
+                    "Invalid target '{}': '{}' is neither a directory nor OWNER/REPO",
+                    spec, location
+                ));
+            }
+        };
+        if host.is_empty() || owner.is_empty() || repo.is_empty() {
+            return Err(format!(
+                "Invalid target '{}': expected [HOST/]OWNER/REPO@BRANCH",
+                spec
+            ));
+        }
+        let branch = branch.ok_or_else(|| {
+            format!(
+                "Invalid target '{}': a branch is required for repositories, e.g. {}@main",
+                spec, location
+            )
+        })?;
+
+        Ok(Target::Remote {
+            host: host.to_ascii_lowercase(),
+            repo: RepoSlug {
+                owner: owner.to_string(),
+                repo: repo.to_string(),
+            },
+            branch,
+        })
+    }
+}
+
+impl TargetConfig {
+    /// Converts the configuration entry into a target, checking that it is complete.
+    pub fn to_target(&self) -> Result<Target, String> {
+        let branch = self
+            .branch
+            .as_ref()
+            .filter(|branch| !branch.trim().is_empty());
+
+        match (&self.path, &self.repo) {
+            (Some(path), None) => {
+                if self.host.is_some() {
+                    return Err(format!(
+                        "target {}: host can only be set together with repo",
+                        path.display()
+                    ));
+                }
+                if !path.is_dir() {
+                    return Err(format!(
+                        "target {}: path is not a directory",
+                        path.display()
+                    ));
+                }
+                Ok(Target::Local {
+                    path: path.clone(),
+                    branch: branch.cloned(),
+                })
+            }
+            (None, Some(repo)) => {
+                let repo_slug = RepoSlug::from_full_name(repo)
+                    .ok_or_else(|| format!("target {}: repo must be in owner/repo form", repo))?;
+                let branch = branch
+                    .ok_or_else(|| format!("target {}: branch is required with repo", repo))?;
+                Ok(Target::Remote {
+                    host: self
+                        .host
+                        .as_deref()
+                        .unwrap_or(GITHUB_HOST)
+                        .to_ascii_lowercase(),
+                    repo: repo_slug,
+                    branch: branch.clone(),
+                })
+            }
+            (Some(_), Some(_)) => Err("target: set either path or repo, not both".to_string()),
+            (None, None) => Err("target: either path or repo must be set".to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Target::Local { path, branch } => {
+                write!(f, "{}", path.display())?;
+                if let Some(branch) = branch {
+                    write!(f, "@{}", branch)?;
+                }
+                Ok(())
+            }
+            Target::Remote { host, repo, branch } if host == GITHUB_HOST => {
+                write!(f, "{}@{}", repo, branch)
+            }
+            Target::Remote { host, repo, branch } => write!(f, "{}/{}@{}", host, repo, branch),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+    use std::fs;
+
+    fn local(path: &Path, branch: Option<&str>) -> Target {
+        Target::Local {
+            path: path.to_path_buf(),
+            branch: branch.map(str::to_string),
+        }
+    }
+
+    fn remote(host: &str, owner: &str, repo: &str, branch: &str) -> Target {
+        Target::Remote {
+            host: host.to_string(),
+            repo: RepoSlug {
+                owner: owner.to_string(),
+                repo: repo.to_string(),
+            },
+            branch: branch.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_directories_and_repositories() {
+        let dir = TempDir::new("target-parse");
+        let checkout = dir.path().join("app@v2");
+        fs::create_dir_all(&checkout).unwrap();
+        let spec = |suffix: &str| format!("{}{}", checkout.display(), suffix);
+
+        let cases = [
+            // A directory whose name contains `@` is not split.
+            (spec(""), Ok(local(&checkout, None))),
+            (spec("@main"), Ok(local(&checkout, Some("main")))),
+            (spec("@feature/x"), Ok(local(&checkout, Some("feature/x")))),
+            (
+                "owner/repo@main".to_string(),
+                Ok(remote("github.com", "owner", "repo", "main")),
+            ),
+            (
+                "owner/repo@feature/x".to_string(),
+                Ok(remote("github.com", "owner", "repo", "feature/x")),
+            ),
+            (
+                "GHE.Example.com/owner/repo@main".to_string(),
+                Ok(remote("ghe.example.com", "owner", "repo", "main")),
+            ),
+            (
+                "owner/repo".to_string(),
+                Err(
+                    "Invalid target 'owner/repo': a branch is required for repositories, \
+                     e.g. owner/repo@main",
+                ),
+            ),
+            (
+                "@main".to_string(),
+                Err("Invalid target '@main': missing path or repository"),
+            ),
+            (
+                "host/owner/repo/extra@main".to_string(),
+                Err(
+                    "Invalid target 'host/owner/repo/extra@main': 'host/owner/repo/extra' \
+                     is neither a directory nor OWNER/REPO",
+                ),
+            ),
+            (
+                "owner/@main".to_string(),
+                Err("Invalid target 'owner/@main': expected [HOST/]OWNER/REPO@BRANCH"),
+            ),
+        ];
+        for (spec, expected) in cases {
+            assert_eq!(
+                Target::parse(&spec),
+                expected.map_err(str::to_string),
+                "parsing {}",
+                spec
+            );
+        }
+    }
+
+    #[test]
+    fn configured_targets_are_checked() {
+        let dir = TempDir::new("target-config");
+        let missing = dir.path().join("missing");
+        let path = |path: &Path, branch: Option<&str>| TargetConfig {
+            path: Some(path.to_path_buf()),
+            branch: branch.map(str::to_string),
+            ..TargetConfig::default()
+        };
+        let repo = |repo: &str, host: Option<&str>, branch: Option<&str>| TargetConfig {
+            repo: Some(repo.to_string()),
+            host: host.map(str::to_string),
+            branch: branch.map(str::to_string),
+            ..TargetConfig::default()
+        };
+
+        let cases = [
+            (path(dir.path(), None), Ok(local(dir.path(), None))),
+            (
+                path(dir.path(), Some("main")),
+                Ok(local(dir.path(), Some("main"))),
+            ),
+            (path(dir.path(), Some(" ")), Ok(local(dir.path(), None))),
+            (
+                path(&missing, None),
+                Err(format!(
+                    "target {}: path is not a directory",
+                    missing.display()
+                )),
+            ),
+            (
+                TargetConfig {
+                    host: Some("ghe.example.com".to_string()),
+                    ..path(dir.path(), None)
+                },
+                Err(format!(
+                    "target {}: host can only be set together with repo",
+                    dir.path().display()
+                )),
+            ),
+            (
+                repo("owner/repo", None, Some("main")),
+                Ok(remote("github.com", "owner", "repo", "main")),
+            ),
+            (
+                repo("owner/repo", Some("GHE.Example.com"), Some("main")),
+                Ok(remote("ghe.example.com", "owner", "repo", "main")),
+            ),
+            (
+                repo("owner/repo", None, None),
+                Err("target owner/repo: branch is required with repo".to_string()),
+            ),
+            (
+                repo("owner/repo", None, Some("")),
+                Err("target owner/repo: branch is required with repo".to_string()),
+            ),
+            (
+                repo("owner", None, Some("main")),
+                Err("target owner: repo must be in owner/repo form".to_string()),
+            ),
+            (
+                TargetConfig {
+                    repo: Some("owner/repo".to_string()),
+                    ..path(dir.path(), Some("main"))
+                },
+                Err("target: set either path or repo, not both".to_string()),
+            ),
+            (
+                TargetConfig::default(),
+                Err("target: either path or repo must be set".to_string()),
+            ),
+        ];
+        for (config, expected) in cases {
+            assert_eq!(config.to_target(), expected, "converting {:?}", config);
+        }
+    }
+}
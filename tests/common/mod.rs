@@ -0,0 +1,30 @@
+//! Helpers shared by the integration tests.
+
+use serde_json::json;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Starts a GitHub Enterprise API at `/api/v3` serving `octo/hello`, which has no workflow
+/// runs and no pull requests.
+pub async fn mock_github() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/repos/octo/hello"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "name": "hello",
+            "full_name": "octo/hello"
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/repos/octo/hello/actions/runs"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "workflow_runs": [] })))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/repos/octo/hello/pulls"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+        .mount(&server)
+        .await;
+    server
+}
@@ -0,0 +1,488 @@
+use reposouls::github::{
+    GitHubClient, GitHubError, RetryPolicy, ReviewState, SharedRateLimit, WorkflowRunStatus,
+    api_base_for_host,
+};
+use serde_json::json;
+use std::time::Duration;
+use wiremock::matchers::{header, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn client_for(server: &MockServer, api_path: &str) -> GitHubClient {
+    GitHubClient::new(
+        format!("{}{}", server.uri(), api_path),
+        "octo".to_string(),
+        "hello".to_string(),
+        "secret-token".to_string(),
+    )
+}
+
+#[test]
+fn api_base_maps_hosts_to_endpoints() {
+    assert_eq!(
+        api_base_for_host("github.com", None),
+        "https://api.github.com"
+    );
+    assert_eq!(
+        api_base_for_host("GitHub.com", None),
+        "https://api.github.com"
+    );
+    assert_eq!(
+        api_base_for_host("github.example.com", None),
+        "https://github.example.com/api/v3"
+    );
+    assert_eq!(
+        api_base_for_host("github.example.com", Some(8443)),
+        "https://github.example.com:8443/api/v3"
+    );
+}
+
+#[tokio::test]
+async fn requests_go_to_the_configured_enterprise_base() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/repos/octo/hello/pulls"))
+        .and(query_param("head", "octo:feature"))
+        .and(header("Authorization", "Bearer secret-token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([{
+            "id": 1,
+            "number": 42,
+            "title": "Add retry logic",
+            "html_url": "https://github.example.com/octo/hello/pull/42",
+            "merged": null,
+            "merged_at": null,
+            "created_at": "2024-05-01T10:00:00Z",
+            "updated_at": "2024-05-01T10:00:00Z"
+        }])))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = client_for(&server, "/api/v3/");
+    let pr = client
+        .get_pr_for_branch("octo", "feature")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(pr.number, 42);
+    assert_eq!(pr.title, "Add retry logic");
+}
+
+#[tokio::test]
+async fn reviews_and_workflow_runs_are_fetched_from_the_base() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/repos/octo/hello/pulls/42/reviews"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([{
+            "id": 7,
+            "state": "APPROVED",
+            "body": "",
+            "html_url": "https://github.example.com/octo/hello/pull/42#pullrequestreview-7",
+            "user": { "login": "alice" },
+            "submitted_at": "2024-05-01T10:00:00Z"
+        }])))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/repos/octo/hello/actions/runs"))
+        .and(query_param("branch", "feature"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "workflow_runs": [{
+                "id": 9,
+                "name": "build-and-test",
+                "html_url": "https://github.example.com/octo/hello/actions/runs/9",
+                "head_branch": "feature",
+                "actor": { "login": "bob" },
+                "status": "completed",
+                "conclusion": "success",
+                "created_at": "2024-05-01T10:00:00Z",
+                "updated_at": "2024-05-01T10:05:00Z"
+            }]
+        })))
+        .mount(&server)
+        .await;
+
+    let client = client_for(&server, "/api/v3");
+    let reviews = client.get_pr_reviews(42).await.unwrap();
+    assert_eq!(reviews.len(), 1);
+    assert_eq!(reviews[0].state, ReviewState::Approved);
+    assert_eq!(reviews[0].user.as_ref().unwrap().login, "alice");
+
+    let runs = client
+        .get_workflow_runs_for_branch("feature", chrono::Utc::now())
+        .await
+        .unwrap();
+    assert_eq!(runs.len(), 1);
+    assert_eq!(runs[0].status, WorkflowRunStatus::Completed);
+    assert_eq!(runs[0].name.as_deref(), Some("build-and-test"));
+}
+
+#[tokio::test]
+async fn api_errors_include_the_status() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/repos/octo/hello/pulls/42"))
+        .respond_with(ResponseTemplate::new(404).set_body_string("Not Found"))
+        .mount(&server)
+        .await;
+
+    let client = client_for(&server, "/api/v3");
+    let err = client.get_pr_details(42).await.unwrap_err();
+    assert!(
+        matches!(&err, GitHubError::NotFound { body, .. } if body == "Not Found"),
+        "unexpected error: {:?}",
+        err
+    );
+    assert!(err.to_string().contains("404"), "unexpected error: {}", err);
+}
+
+#[tokio::test]
+async fn comments_are_read_from_every_page_since_the_given_time() {
+    let server = MockServer::start().await;
+    let comment = |id: i64| {
+        json!({
+            "id": id,
+            "body": "Looks good",
+            "html_url": format!("https://github.example.com/octo/hello/pull/42#issuecomment-{}", id),
+            "user": { "login": "alice" },
+            "created_at": "2024-05-01T10:00:00Z"
+        })
+    };
+    Mock::given(method("GET"))
+        .and(path("/api/v3/repos/octo/hello/issues/42/comments"))
+        .and(query_param("since", "2024-05-01T09:00:00Z"))
+        .and(query_param("per_page", "100"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header(
+                    "Link",
+                    format!(
+                        "<{}/api/v3/repos/octo/hello/issues/42/comments?page=2>; rel=\"next\", \
+                         <{}/api/v3/repos/octo/hello/issues/42/comments?page=2>; rel=\"last\"",
+                        server.uri(),
+                        server.uri()
+                    )
+                    .as_str(),
+                )
+                .set_body_json(json!([comment(1), comment(2)])),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/repos/octo/hello/issues/42/comments"))
+        .and(query_param("page", "2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([comment(3)])))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = client_for(&server, "/api/v3");
+    let since = "2024-05-01T09:00:00Z".parse().unwrap();
+    let comments = client.get_pr_comments(42, since).await.unwrap();
+    let ids: Vec<i64> = comments.iter().map(|comment| comment.id).collect();
+    assert_eq!(ids, [1, 2, 3]);
+}
+
+#[tokio::test]
+async fn next_pages_outside_the_api_are_not_followed() {
+    let server = MockServer::start().await;
+    let client = client_for(&server, "/api/v3");
+    let uri = server.uri();
+    let port = server.address().port();
+    let foreign = [
+        // Shares the base as a string prefix, but the host is `evil.example`.
+        format!(
+            "{}@evil.example/api/v3/repos/octo/hello/issues/42/comments?page=2",
+            uri
+        ),
+        format!(
+            "{}.evil.example/api/v3/repos/octo/hello/issues/42/comments?page=2",
+            uri
+        ),
+        format!(
+            "{}/api/v3-evil/repos/octo/hello/issues/42/comments?page=2",
+            uri
+        ),
+        format!(
+            "http://127.0.0.1:{}/api/v3/repos/octo/hello/issues/42/comments?page=2",
+            port + 1
+        ),
+        format!(
+            "https://127.0.0.1:{}/api/v3/repos/octo/hello/issues/42/comments?page=2",
+            port
+        ),
+    ];
+    for next in foreign {
+        server.reset().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/repos/octo/hello/issues/42/comments"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("Link", format!("<{}>; rel=\"next\"", next).as_str())
+                    .set_body_json(json!([])),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let since = "2024-05-01T09:00:00Z".parse().unwrap();
+        let err = client.get_pr_comments(42, since).await.unwrap_err();
+        assert!(
+            matches!(&err, GitHubError::ForeignNextPage(url) if *url == next),
+            "followed {}: {:?}",
+            next,
+            err
+        );
+    }
+}
+
+#[tokio::test]
+async fn unchanged_responses_are_revalidated_with_their_etag() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/repos/octo/hello/pulls/42/reviews"))
+        .and(header("If-None-Match", "\"reviews-v1\""))
+        .respond_with(ResponseTemplate::new(304))
+        .with_priority(1)
+        .expect(2)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/repos/octo/hello/pulls/42/reviews"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("ETag", "\"reviews-v1\"")
+                .set_body_json(json!([{
+                    "id": 7,
+                    "state": "CHANGES_REQUESTED",
+                    "body": "Please add tests",
+                    "html_url": "https://github.example.com/octo/hello/pull/42#pullrequestreview-7",
+                    "user": { "login": "alice" },
+                    "submitted_at": "2024-05-01T10:00:00Z"
+                }])),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = client_for(&server, "/api/v3");
+    for _ in 0..3 {
+        let reviews = client.get_pr_reviews(42).await.unwrap();
+        assert_eq!(reviews.len(), 1);
+        assert_eq!(reviews[0].id, 7);
+        assert_eq!(reviews[0].state, ReviewState::ChangesRequested);
+    }
+}
+
+#[tokio::test]
+async fn moving_the_cursor_replaces_the_cached_listing() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/repos/octo/hello/issues/42/comments"))
+        .and(header("If-None-Match", "\"comments-v1\""))
+        .respond_with(ResponseTemplate::new(304))
+        .with_priority(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/repos/octo/hello/issues/42/comments"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("ETag", "\"comments-v1\"")
+                .set_body_json(json!([])),
+        )
+        .expect(3)
+        .mount(&server)
+        .await;
+
+    let client = client_for(&server, "/api/v3");
+    let earlier = "2024-05-01T09:00:00Z".parse().unwrap();
+    let later = "2024-05-01T09:10:00Z".parse().unwrap();
+    // Only a request for the same URL is conditional.
+    client.get_pr_comments(42, earlier).await.unwrap();
+    client.get_pr_comments(42, earlier).await.unwrap();
+    client.get_pr_comments(42, later).await.unwrap();
+    // The response for the earlier cursor was replaced, not kept alongside.
+    client.get_pr_comments(42, earlier).await.unwrap();
+}
+
+#[tokio::test]
+async fn exhausted_rate_limit_pauses_requests_until_reset() {
+    let server = MockServer::start().await;
+    let reset = chrono::Utc::now().timestamp() + 600;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/repos/octo/hello/pulls/42/reviews"))
+        .respond_with(
+            ResponseTemplate::new(403)
+                .insert_header("X-RateLimit-Limit", "5000")
+                .insert_header("X-RateLimit-Remaining", "0")
+                .insert_header("X-RateLimit-Reset", reset.to_string().as_str())
+                .set_body_string("API rate limit exceeded"),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = client_for(&server, "/api/v3");
+    let err = client.get_pr_reviews(42).await.unwrap_err();
+    assert!(
+        matches!(err, GitHubError::RateLimited { status: Some(status), .. } if status == 403),
+        "unexpected error: {:?}",
+        err
+    );
+
+    let rate_limit = client.rate_limit().unwrap();
+    assert_eq!(rate_limit.limit, 5000);
+    assert_eq!(rate_limit.remaining, 0);
+    assert_eq!(
+        client.rate_limited_until().map(|until| until.timestamp()),
+        Some(reset)
+    );
+
+    // Further requests fail without reaching the server.
+    assert!(matches!(
+        client.get_pr_reviews(42).await,
+        Err(GitHubError::RateLimited { status: None, .. })
+    ));
+}
+
+#[tokio::test]
+async fn clients_of_one_token_share_its_rate_limit() {
+    let server = MockServer::start().await;
+    let reset = chrono::Utc::now().timestamp() + 600;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/repos/octo/hello/pulls/42/reviews"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("X-RateLimit-Limit", "5000")
+                .insert_header("X-RateLimit-Remaining", "4000")
+                .insert_header("X-RateLimit-Reset", reset.to_string().as_str())
+                .set_body_json(json!([])),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/repos/octo/other/pulls/42/reviews"))
+        .respond_with(
+            ResponseTemplate::new(429)
+                .insert_header("Retry-After", "60")
+                .set_body_string("secondary rate limit"),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let rate_limit = SharedRateLimit::default();
+    let client = |repo: &str| {
+        GitHubClient::with_shared_rate_limit(
+            format!("{}/api/v3", server.uri()),
+            "octo".to_string(),
+            repo.to_string(),
+            "secret-token".to_string(),
+            RetryPolicy::default(),
+            rate_limit.clone(),
+        )
+    };
+    let hello = client("hello");
+    let other = client("other");
+    assert_eq!(hello.sharing_clients(), 2);
+
+    hello.get_pr_reviews(42).await.unwrap();
+    hello.get_pr_reviews(42).await.unwrap();
+    assert_eq!(hello.take_request_count(), 2);
+    assert_eq!(hello.take_request_count(), 0);
+    assert_eq!(
+        other.rate_limit().map(|budget| budget.remaining),
+        Some(4000)
+    );
+
+    // A client refused for exceeding the limit pauses every client on the token.
+    assert!(other.get_pr_reviews(42).await.is_err());
+    assert!(hello.rate_limited_until().is_some());
+    assert!(matches!(
+        hello.get_pr_reviews(42).await,
+        Err(GitHubError::RateLimited { status: None, .. })
+    ));
+
+    drop(other);
+    assert_eq!(hello.sharing_clients(), 1);
+}
+
+fn fast_retries() -> RetryPolicy {
+    RetryPolicy {
+        timeout: Duration::from_secs(5),
+        retries: 2,
+        base_delay: Duration::from_millis(10),
+        max_delay: Duration::from_millis(20),
+    }
+}
+
+#[tokio::test]
+async fn unavailable_responses_are_retried() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/repos/octo/hello"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(2)
+        .with_priority(1)
+        .expect(2)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/repos/octo/hello"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "name": "hello",
+            "full_name": "octo/hello"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = GitHubClient::with_retry_policy(
+        format!("{}/api/v3", server.uri()),
+        "octo".to_string(),
+        "hello".to_string(),
+        "secret-token".to_string(),
+        fast_retries(),
+    );
+    let repository = client.get_repository().await.unwrap();
+    assert_eq!(repository.full_name, "octo/hello");
+    assert!(!client.is_offline());
+}
+
+#[tokio::test]
+async fn unreachable_api_marks_the_client_offline() {
+    // Nothing listens on the discard port, so connections are refused.
+    let client = GitHubClient::with_retry_policy(
+        "http://127.0.0.1:9".to_string(),
+        "octo".to_string(),
+        "hello".to_string(),
+        "secret-token".to_string(),
+        fast_retries(),
+    );
+    assert!(matches!(
+        client.get_repository().await,
+        Err(GitHubError::Network(_))
+    ));
+    assert!(client.is_offline());
+}
+
+#[tokio::test]
+async fn rejected_tokens_are_reported_as_unauthorized() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v3/repos/octo/hello"))
+        .respond_with(ResponseTemplate::new(401).set_body_string("Bad credentials"))
+        .mount(&server)
+        .await;
+
+    let client = client_for(&server, "/api/v3");
+    let err = client.get_repository().await.unwrap_err();
+    assert!(
+        matches!(err, GitHubError::Unauthorized { .. }),
+        "unexpected error: {:?}",
+        err
+    );
+    assert_eq!(err.status().map(|status| status.as_u16()), Some(401));
+}
@@ -0,0 +1,81 @@
+// The configuration is placed through XDG_CONFIG_HOME, which only Linux honours.
+#![cfg(all(target_os = "linux", feature = "cli", feature = "headless"))]
+
+mod common;
+
+use reposouls::shutdown::SHUTDOWN_TIMEOUT;
+use std::env;
+use std::fs;
+use std::io::Read;
+use std::process::{self, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::runtime::Runtime;
+
+#[test]
+fn sigterm_stops_a_headless_run() {
+    let home = env::temp_dir().join(format!("reposouls-headless-{}", process::id()));
+    let config_dir = home.join("config/reposouls");
+    let state_path = home.join("data/reposouls/state/github.com/octo/hello/main.json");
+    fs::create_dir_all(&config_dir).unwrap();
+
+    let runtime = Runtime::new().unwrap();
+    let server = runtime.block_on(common::mock_github());
+    fs::write(
+        config_dir.join("config.toml"),
+        format!(
+            "[hosts.\"github.com\"]\napi_base = \"{}/api/v3\"\n",
+            server.uri()
+        ),
+    )
+    .unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_reposouls"))
+        .args(["--headless", "octo/hello@main"])
+        .current_dir(&home)
+        .env("HOME", &home)
+        .env("XDG_CONFIG_HOME", home.join("config"))
+        .env("XDG_DATA_HOME", home.join("data"))
+        .env("GITHUB_TOKEN", "secret-token")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    // The state is saved after the first poll, then reposouls waits for the next one.
+    let started = Instant::now();
+    while !state_path.exists() {
+        assert!(
+            started.elapsed() < Duration::from_secs(10),
+            "the first poll did not save the state"
+        );
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    unsafe { libc::kill(child.id() as libc::pid_t, libc::SIGTERM) };
+    let signalled = Instant::now();
+    let exit = loop {
+        if let Some(exit) = child.try_wait().unwrap() {
+            break exit;
+        }
+        if signalled.elapsed() > SHUTDOWN_TIMEOUT {
+            child.kill().unwrap();
+            panic!("reposouls did not exit in time");
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+    assert!(exit.success(), "unexpected exit: {}", exit);
+
+    let mut stdout = String::new();
+    child
+        .stdout
+        .take()
+        .unwrap()
+        .read_to_string(&mut stdout)
+        .unwrap();
+    assert!(stdout.contains("Shutting down..."), "stdout: {}", stdout);
+    assert!(stdout.contains("Stopped"), "stdout: {}", stdout);
+
+    drop(server);
+    let _ = fs::remove_dir_all(&home);
+}
@@ -0,0 +1,85 @@
+#![cfg(all(unix, feature = "cli"))]
+
+mod common;
+
+use reposouls::auth::Credentials;
+use reposouls::config::{Config, HostConfig};
+use reposouls::events::{NotificationEvent, run_event_checker};
+use reposouls::shutdown::{SHUTDOWN_TIMEOUT, handle_signals};
+use reposouls::state::SavedState;
+use reposouls::status::Status;
+use reposouls::target::Target;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::process;
+use std::sync::{Arc, mpsc};
+use std::time::Duration;
+use tokio::runtime::Runtime;
+use tokio::time;
+
+/// Runs the event checker without the GUI and stops it with SIGTERM.
+#[test]
+fn sigterm_stops_the_checker_after_saving_its_state() {
+    let home = env::temp_dir().join(format!("reposouls-shutdown-{}", process::id()));
+    // The environment is only safe to change before other threads start.
+    unsafe {
+        env::set_var("HOME", &home);
+        env::set_var("XDG_DATA_HOME", home.join("data"));
+        env::set_var("GITHUB_TOKEN", "secret-token");
+    }
+    let state_path = dirs::data_dir()
+        .unwrap()
+        .join("reposouls/state/github.com/octo/hello/main.json");
+
+    Runtime::new().unwrap().block_on(async {
+        let server = common::mock_github().await;
+        let config = Config {
+            hosts: HashMap::from([(
+                "github.com".to_string(),
+                HostConfig {
+                    api_base: Some(format!("{}/api/v3", server.uri())),
+                },
+            )]),
+            ..Config::default()
+        };
+        let status = Arc::new(Status::new());
+        handle_signals(Arc::clone(&status)).unwrap();
+
+        let (sender, _receiver) = mpsc::channel::<NotificationEvent>();
+        let checker = tokio::spawn(run_event_checker(
+            Arc::new(sender),
+            config,
+            vec![Target::parse("octo/hello@main").unwrap()],
+            Arc::clone(&status),
+            Arc::new(Credentials::new()),
+        ));
+
+        // The state is saved after the first poll, then the checker waits for the next one.
+        time::timeout(Duration::from_secs(10), async {
+            while !state_path.exists() {
+                time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .expect("the first poll did not save the state");
+        fs::remove_file(&state_path).unwrap();
+
+        unsafe { libc::kill(libc::getpid(), libc::SIGTERM) };
+        let result = time::timeout(SHUTDOWN_TIMEOUT, checker)
+            .await
+            .expect("the checker did not stop in time");
+        assert!(
+            matches!(result, Ok(Ok(()))),
+            "unexpected result: {:?}",
+            result
+        );
+        assert!(status.is_shutting_down());
+
+        // The state of the last poll is saved again on the way out.
+        let saved = SavedState::load(&state_path).unwrap();
+        assert!(saved.is_some(), "the state was not saved on shutdown");
+    });
+
+    let _ = fs::remove_dir_all(&home);
+}